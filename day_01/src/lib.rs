@@ -1,18 +1,31 @@
+use std::convert::Infallible;
+
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 24000;
+pub const EXAMPLE_PART2: u64 = 45000;
 
+#[derive(Serialize)]
 pub struct Solution {
     elves: Vec<Vec<u64>>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = u64;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         prelude::init();
 
         let mut elves = vec![];
         let mut elf = vec![];
 
-        for line in input.lines() {
+        for (i, line) in input.lines().enumerate() {
             prelude::log::debug!("got: {:?}", line);
 
             if line.is_empty() {
@@ -21,26 +34,80 @@ impl Solution {
                 continue;
             }
 
-            elf.push(line.parse().expect("input was bad"));
+            elf.push(parse_word(i + 1, line, line, "a number of calories")?);
         }
         elves.push(elf);
 
         debug!("elves: {:?}", elves);
 
-        Solution { elves }
+        Ok(Solution { elves })
     }
 
-    pub fn part1(&self) -> u64 {
-        self.elves
+    fn part1(&self) -> Result<u64, Infallible> {
+        Ok(self
+            .elves
             .iter()
             .map(|elf| elf.iter().sum())
             .max()
-            .expect("there weren't any elves?")
+            .expect("there weren't any elves?"))
     }
 
-    pub fn part2(&self) -> u64 {
+    fn part2(&self) -> Result<u64, Infallible> {
         let mut calories = self.elves.iter().map(|elf| elf.iter().sum()).collect_vec();
         calories.sort_by_key(|&cal| std::cmp::Reverse(cal));
-        calories.iter().take(3).sum()
+        Ok(calories.iter().take(3).sum())
     }
 }
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 250;
+
+    /// `size` elves, each carrying a handful of snacks.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                (0..rng.gen_range(1..=15))
+                    .map(|_| rng.gen_range(1000..=60000).to_string())
+                    .join("\n")
+            })
+            .join("\n\n")
+            + "\n"
+    }
+}
+
+// the slow way: adds up every elf's snacks over again for each comparison, and tries every three
+// elves together instead of sorting
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    fn total(elf: &[u64]) -> u64 {
+        elf.iter().sum()
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u64, Infallible> {
+            let most = self
+                .elves
+                .iter()
+                .find(|elf| self.elves.iter().all(|other| total(elf) >= total(other)))
+                .unwrap();
+            Ok(total(most))
+        }
+
+        fn reference_part2(&self) -> Result<u64, Infallible> {
+            Ok(self
+                .elves
+                .iter()
+                .combinations(self.elves.len().min(3))
+                .map(|elves| elves.into_iter().map(|elf| total(elf)).sum())
+                .max()
+                .unwrap())
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+prelude::differential_tests!(20);
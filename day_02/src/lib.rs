@@ -1,10 +1,18 @@
+use std::convert::Infallible;
+
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 use std::cmp::Ordering::*;
 use Choice::*;
 use RoundEnd::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i64 = 15;
+pub const EXAMPLE_PART2: i64 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 enum Choice {
     Rock,
     Paper,
@@ -27,20 +35,21 @@ impl std::cmp::Ord for Choice {
     }
 }
 
-impl TryFrom<&str> for Choice {
-    type Error = ();
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
+impl Choice {
+    fn parse(
+        line_number: usize,
+        line: &str,
+        word: &str,
+        expected: &str,
+    ) -> Result<Self, ParseError> {
+        match word {
             "A" | "X" => Ok(Rock),
             "B" | "Y" => Ok(Paper),
             "C" | "Z" => Ok(Scissors),
-            _ => Err(()),
+            _ => Err(ParseError::at(line_number, line, word, expected)),
         }
     }
-}
 
-impl Choice {
     fn shape(&self) -> i64 {
         match self {
             Rock => 1,
@@ -85,31 +94,40 @@ impl RoundEnd {
     }
 }
 
+#[derive(Serialize)]
 pub struct Solution {
     strategy_guide: Vec<(Choice, Choice)>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = i64;
+    type Part2 = i64;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         prelude::init();
 
         let strategy_guide = input
             .lines()
-            .map(|line| {
-                let data = line.split(" ").collect_vec();
-                let opponent = data[0].try_into().unwrap();
-                let me = data[1].try_into().unwrap();
-                (opponent, me)
+            .enumerate()
+            .map(|(i, line)| {
+                let Some((opponent, me)) = line.split_once(' ') else {
+                    return Err(ParseError::end_of_line(i + 1, line, "a space"));
+                };
+                let opponent = Choice::parse(i + 1, line, opponent, "A, B, or C")?;
+                let me = Choice::parse(i + 1, line, me, "X, Y, or Z")?;
+                Ok((opponent, me))
             })
-            .collect_vec();
+            .collect::<Result<Vec<_>, _>>()?;
 
         debug!("{:?}", strategy_guide);
 
-        Solution { strategy_guide }
+        Ok(Solution { strategy_guide })
     }
 
-    pub fn part1(&self) -> i64 {
-        self.strategy_guide
+    fn part1(&self) -> Result<i64, Infallible> {
+        Ok(self
+            .strategy_guide
             .iter()
             .map(|(opponent, me)| {
                 let shape = me.shape();
@@ -124,11 +142,12 @@ impl Solution {
 
                 shape + result
             })
-            .sum()
+            .sum())
     }
 
-    pub fn part2(&self) -> i64 {
-        self.strategy_guide
+    fn part2(&self) -> Result<i64, Infallible> {
+        Ok(self
+            .strategy_guide
             .iter()
             .map(|(opponent, me)| {
                 let round_end = RoundEnd::from(me);
@@ -142,6 +161,73 @@ impl Solution {
 
                 me.shape() + result
             })
-            .sum()
+            .sum())
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 2500;
+
+    /// `size` rounds.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{} {}\n",
+                    ['A', 'B', 'C'][rng.gen_range(0..3)],
+                    ['X', 'Y', 'Z'][rng.gen_range(0..3)]
+                )
+            })
+            .collect()
+    }
+}
+
+// the slow way: scores a round by counting around the circle from the opponent's shape instead of
+// comparing the shapes, and picks part 2's shape by trying all three until one ends right
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    fn index(choice: &Choice) -> i64 {
+        choice.shape() - 1
+    }
+
+    // 0 for a loss, 1 for a draw, 2 for a win: one step around the circle from the opponent wins,
+    // two steps around loses
+    fn outcome(opponent: &Choice, me: &Choice) -> i64 {
+        (index(me) - index(opponent) + 4) % 3
+    }
+
+    fn score(opponent: &Choice, me: &Choice) -> i64 {
+        me.shape() + 3 * outcome(opponent, me)
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i64, Infallible> {
+            Ok(self
+                .strategy_guide
+                .iter()
+                .map(|(opponent, me)| score(opponent, me))
+                .sum())
+        }
+
+        fn reference_part2(&self) -> Result<i64, Infallible> {
+            Ok(self
+                .strategy_guide
+                .iter()
+                .map(|(opponent, end)| {
+                    let me = [Rock, Paper, Scissors]
+                        .into_iter()
+                        .find(|me| outcome(opponent, me) == index(end))
+                        .unwrap();
+                    score(opponent, &me)
+                })
+                .sum())
+        }
     }
 }
+
+prelude::example_tests!();
+prelude::generator_tests!(50);
+prelude::parser_tests!(50);
+prelude::differential_tests!(50);
@@ -1,6 +1,16 @@
+use std::convert::Infallible;
+
 use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 157;
+pub const EXAMPLE_PART2: u64 = 70;
 
+#[derive(Serialize)]
 pub struct Solution {
     rucksacks: Vec<(Vec<u8>, Vec<u8>)>,
 }
@@ -13,53 +23,216 @@ fn priority(c: u8) -> u64 {
     }
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = u64;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
-        let rucksacks = input
-            .lines()
-            .map(|line| {
-                let line = line.as_bytes();
-                let (first, second) = line.split_at(line.len() / 2);
+        let lines = input.lines().collect_vec();
+        let rucksacks = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if let Some(position) = line.find(|c: char| !c.is_ascii_alphabetic()) {
+                    return Err(ParseError::at(
+                        i + 1,
+                        line,
+                        &line[position..],
+                        "an item letter",
+                    ));
+                }
+                if line.len() % 2 != 0 {
+                    return Err(ParseError::end_of_line(
+                        i + 1,
+                        line,
+                        "an even number of items",
+                    ));
+                }
+
+                let items = line.as_bytes();
+                let (first, second) = items.split_at(items.len() / 2);
+                if in_common([first, second]).len() != 1 {
+                    return Err(ParseError::at(
+                        i + 1,
+                        line,
+                        line,
+                        "exactly one type of item in both compartments",
+                    ));
+                }
 
-                (first.to_vec(), second.to_vec())
+                Ok((first.to_vec(), second.to_vec()))
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // and every three elves are a group, with exactly one badge between them
+        for (group, rucksacks) in rucksacks.chunks(3).enumerate() {
+            let [elf, alf, ulf] = rucksacks else {
+                return Err(ParseError::end_of_input(
+                    lines.len() + 1,
+                    "another rucksack, to finish the group of three",
+                ));
+            };
+            if in_common([elf, alf, ulf].map(contents)).len() != 1 {
+                let line_number = group * 3 + 3;
+                let line = lines[line_number - 1];
+                return Err(ParseError::at(
+                    line_number,
+                    line,
+                    line,
+                    "exactly one type of item in common with the two rucksacks before it",
+                ));
+            }
+        }
 
         debug!("rucksacks: {:x?}", rucksacks);
 
-        Self { rucksacks }
+        Ok(Self { rucksacks })
     }
 
-    pub fn part1(&self) -> u64 {
-        self.rucksacks
+    fn part1(&self) -> Result<u64, Infallible> {
+        // there's exactly one item in both compartments, so that's all this adds up
+        Ok(self
+            .rucksacks
             .iter()
-            .map(|(first, second)| {
-                let first = first.iter().collect::<HashSet<_>>();
-                let second = second.iter().collect::<HashSet<_>>();
-
-                let overlap = first.intersection(&second).collect_vec();
-                assert_eq!(overlap.len(), 1);
-
-                priority(**overlap[0])
-            })
-            .sum()
+            .flat_map(|(first, second)| in_common([first, second]))
+            .map(priority)
+            .sum())
     }
 
-    pub fn part2(&self) -> u64 {
-        self.rucksacks
+    fn part2(&self) -> Result<u64, Infallible> {
+        // and likewise the badge is the only thing the three of them have in common
+        Ok(self
+            .rucksacks
             .iter()
-            .map(|(first, second)| first.iter().chain(second.iter()).collect::<HashSet<_>>())
+            .map(contents)
             .tuples()
-            .map(|(elf, alf, ulf)| {
-                let all = elf.intersection(&alf).copied().collect::<HashSet<_>>();
-                let all = all.intersection(&ulf).copied().collect_vec();
+            .flat_map(|(elf, alf, ulf)| {
+                let all = in_common([elf, alf, ulf]);
                 debug!("all: {:?}", all);
-                assert_eq!(all.len(), 1);
-
-                priority(*all[0])
+                all
             })
-            .sum()
+            .map(priority)
+            .sum())
+    }
+}
+
+// everything in a rucksack, whichever compartment it's in
+fn contents((first, second): &(Vec<u8>, Vec<u8>)) -> Vec<u8> {
+    [first.as_slice(), second.as_slice()].concat()
+}
+
+// the items that are in every one of `piles`
+fn in_common<T: AsRef<[u8]>, const N: usize>(piles: [T; N]) -> HashSet<u8> {
+    let mut piles = piles
+        .iter()
+        .map(|pile| pile.as_ref().iter().copied().collect::<HashSet<_>>());
+    let first = piles.next().unwrap_or_default();
+    piles.fold(first, |all, pile| &all & &pile)
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 100;
+
+    /// `size` groups of three elves.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let mut input = String::new();
+        let mut letters = (b'a'..=b'z').chain(b'A'..=b'Z').collect_vec();
+
+        for _ in 0..size {
+            // the badge is the only letter the group has in common, so everything else each elf
+            // carries comes out of a separate third of the alphabet
+            letters.shuffle(rng);
+            let (&badge, rest) = letters.split_first().unwrap();
+
+            for pool in rest.chunks(rest.len() / 3) {
+                // and likewise, the letter in both compartments is the only one they share
+                let shared = *pool.choose(rng).unwrap();
+                let (left, right) = pool.split_at(pool.len() / 2);
+                let length = rng.gen_range(2..=16);
+
+                for (side, carried) in [(left, vec![shared, badge]), (right, vec![shared])] {
+                    let mut compartment = carried;
+                    while compartment.len() < length {
+                        compartment.push(*side.choose(rng).unwrap());
+                    }
+                    compartment.shuffle(rng);
+                    input.extend(compartment.into_iter().map(char::from));
+                }
+                input.push('\n');
+            }
+        }
+
+        input
+    }
+}
+
+// the slow way: goes down the alphabet checking each letter against every item, instead of
+// intersecting sets
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    fn letters() -> impl Iterator<Item = u8> {
+        (b'a'..=b'z').chain(b'A'..=b'Z')
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u64, Infallible> {
+            Ok(self
+                .rucksacks
+                .iter()
+                .map(|(first, second)| {
+                    letters()
+                        .filter(|letter| first.contains(letter) && second.contains(letter))
+                        .map(priority)
+                        .sum::<u64>()
+                })
+                .sum())
+        }
+
+        fn reference_part2(&self) -> Result<u64, Infallible> {
+            Ok(self
+                .rucksacks
+                .chunks(3)
+                .map(|group| {
+                    letters()
+                        .filter(|letter| {
+                            group.iter().all(|(first, second)| {
+                                first.contains(letter) || second.contains(letter)
+                            })
+                        })
+                        .map(priority)
+                        .sum::<u64>()
+                })
+                .sum())
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(10);
+prelude::parser_tests!(10);
+prelude::differential_tests!(10);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn not_exactly_one_in_common() {
+        for (input, line) in [
+            // nothing in both compartments
+            ("abcd\n", 1),
+            // each rucksack is fine, but a and b are both in all three
+            ("abac\nabad\nabae\n", 3),
+            // the group isn't finished
+            ("abac\nadae\n", 3),
+        ] {
+            let error = Solution::new(input).err().unwrap();
+            assert_eq!(error.line, line, "{}", input);
+        }
     }
 }
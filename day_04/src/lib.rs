@@ -0,0 +1,146 @@
+use std::convert::Infallible;
+
+use prelude::log::debug;
+use prelude::rand::Rng;
+use prelude::*;
+use serde::Serialize;
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 2;
+pub const EXAMPLE_PART2: usize = 4;
+
+/// The sections an elf has been assigned, from `start` through `end`, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Sections {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Sections {
+    /// Whether every section of `other` is one of these, too.
+    pub fn contains(&self, other: &Sections) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether there's any section in both.
+    pub fn overlaps(&self, other: &Sections) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    // "start-end", which is `word` within `line`
+    fn parse(line_number: usize, line: &str, word: &str) -> Result<Self, ParseError> {
+        let Some((start, end)) = word.split_once('-') else {
+            return Err(ParseError::at(line_number, line, word, "a range like 2-4"));
+        };
+        let start = parse_word(line_number, line, start, "a section number")?;
+        let end = parse_word(line_number, line, end, "a section number")?;
+        if end < start {
+            return Err(ParseError::at(
+                line_number,
+                line,
+                word,
+                "a range that doesn't end before it starts",
+            ));
+        }
+
+        Ok(Sections { start, end })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Solution {
+    pairs: Vec<(Sections, Sections)>,
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = usize;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        init();
+
+        let pairs = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let Some((first, second)) = line.split_once(',') else {
+                    return Err(ParseError::end_of_line(i + 1, line, "a comma"));
+                };
+                Ok((
+                    Sections::parse(i + 1, line, first)?,
+                    Sections::parse(i + 1, line, second)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        debug!("parsed: {:?}", pairs);
+
+        Ok(Solution { pairs })
+    }
+
+    fn part1(&self) -> Result<usize, Infallible> {
+        Ok(self
+            .pairs
+            .iter()
+            .filter(|(first, second)| first.contains(second) || second.contains(first))
+            .count())
+    }
+
+    fn part2(&self) -> Result<usize, Infallible> {
+        Ok(self
+            .pairs
+            .iter()
+            .filter(|(first, second)| first.overlaps(second))
+            .count())
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 1000;
+
+    /// `size` pairs of elves, with sections numbered up to 99.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let mut sections = || {
+            let start = rng.gen_range(1..=99);
+            format!("{}-{}", start, rng.gen_range(start..=99))
+        };
+
+        (0..size)
+            .map(|_| format!("{},{}\n", sections(), sections()))
+            .collect()
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(50);
+prelude::parser_tests!(50);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn sections() -> impl Strategy<Value = Sections> {
+        (0..20u32, 0..20u32).prop_map(|(a, b)| Sections {
+            start: a.min(b),
+            end: a.max(b),
+        })
+    }
+
+    #[test]
+    fn backward_range() {
+        let error = Solution::new("2-4,6-8\n4-2,6-8\n").err().unwrap();
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+
+    proptest! {
+        #[test]
+        fn same_as_sets(a in sections(), b in sections()) {
+            let set = |s: Sections| (s.start..=s.end).collect::<HashSet<_>>();
+            prop_assert_eq!(a.contains(&b), set(b).is_subset(&set(a)));
+            prop_assert_eq!(a.overlaps(&b), !set(a).is_disjoint(&set(b)));
+        }
+    }
+}
@@ -1,32 +1,49 @@
 use std::collections::VecDeque;
+use std::convert::Infallible;
 
 use js_sys::Function;
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Document, HtmlDivElement, HtmlElement, Text};
 
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: &str = "CMZ";
+pub const EXAMPLE_PART2: &str = "MCD";
+
+#[derive(Serialize)]
 pub struct Solution {
     initial: Vec<Vec<u8>>, // stacks of crates from the bottom up
     steps: Vec<(usize, usize, usize)>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = String;
+    type Part2 = String;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
         let lines = input.lines().collect_vec();
         let separator_index = lines
             .iter()
             .position(|line| line.is_empty())
-            .expect("you should've given me an empty line at least");
+            .ok_or_else(|| {
+                ParseError::end_of_input(lines.len() + 1, "an empty line after the crates")
+            })?;
+        if separator_index == 0 {
+            return Err(ParseError::end_of_line(1, lines[0], "the stacks of crates"));
+        }
 
         let stack_count = lines[separator_index - 1]
             .split(' ')
             .filter(|word| !word.is_empty())
             .count();
         let mut stacks = vec![vec![]; stack_count];
-        for line in lines[..=separator_index - 2].iter().rev() {
+        for line in lines[..separator_index - 1].iter().rev() {
             debug!("parsing line {:?}", line);
             for stack_index in 0..stack_count {
                 let container = line.as_bytes().get(stack_index * 4 + 1);
@@ -43,24 +60,105 @@ impl Solution {
 
         debug!("initial stacks: {:x?}", stacks);
 
+        // keep track of how tall each stack gets, so a step can't take more crates than there are
+        let mut heights = stacks.iter().map(Vec::len).collect_vec();
         let steps = lines[separator_index + 1..]
             .iter()
-            .map(|line| {
-                let split = line.split(' ').collect_vec();
-                (
-                    split[1].parse().expect("count"),
-                    split[3].parse().expect("from"),
-                    split[5].parse().expect("to"),
-                )
+            .enumerate()
+            .map(|(i, line)| {
+                let step = parse_step(separator_index + i + 2, line, &heights)?;
+                let (count, from, to) = step;
+                heights[from - 1] -= count;
+                heights[to - 1] += count;
+                Ok(step)
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         debug!("steps: {:?}", steps);
 
-        Solution {
+        Ok(Solution {
             initial: stacks,
             steps,
+        })
+    }
+
+    fn part1(&self) -> Result<String, Infallible> {
+        Ok(self.top_crates(true))
+    }
+
+    fn part2(&self) -> Result<String, Infallible> {
+        Ok(self.top_crates(false))
+    }
+}
+
+// parses "move <count> from <from> to <to>", making sure that there are enough crates to move
+fn parse_step(
+    line_number: usize,
+    line: &str,
+    heights: &[usize],
+) -> Result<(usize, usize, usize), ParseError> {
+    let mut words = line.split(' ');
+    let mut field = |keyword: &str, expected: &str| -> Result<(&str, usize), ParseError> {
+        let expected_keyword = format!("{:?}", keyword);
+        match words.next() {
+            Some(word) if word == keyword => (),
+            Some(word) => return Err(ParseError::at(line_number, line, word, expected_keyword)),
+            None => return Err(ParseError::end_of_line(line_number, line, expected_keyword)),
         }
+        let word = words
+            .next()
+            .ok_or_else(|| ParseError::end_of_line(line_number, line, expected))?;
+        Ok((word, parse_word(line_number, line, word, expected)?))
+    };
+
+    let (count_word, count) = field("move", "a number of crates")?;
+    let (from_word, from) = field("from", "a stack number")?;
+    let (to_word, to) = field("to", "a stack number")?;
+    if let Some(word) = words.next() {
+        return Err(ParseError::at(line_number, line, word, "end of line"));
+    }
+
+    for (word, stack) in [(from_word, from), (to_word, to)] {
+        if !(1..=heights.len()).contains(&stack) {
+            return Err(ParseError::at(
+                line_number,
+                line,
+                word,
+                format!("a stack number from 1 to {}", heights.len()),
+            ));
+        }
+    }
+    if count > heights[from - 1] {
+        return Err(ParseError::at(
+            line_number,
+            line,
+            count_word,
+            format!("at most {} crates", heights[from - 1]),
+        ));
+    }
+
+    Ok((count, from, to))
+}
+
+impl Solution {
+    // moving crates one at a time is the same as moving them all at once, but upside-down
+    fn top_crates(&self, one_at_a_time: bool) -> String {
+        let mut stacks = self.initial.clone();
+
+        for &(count, from, to) in &self.steps {
+            let from = &mut stacks[from - 1];
+            let mut containers = from.split_off(from.len() - count);
+            if one_at_a_time {
+                containers.reverse();
+            }
+            stacks[to - 1].append(&mut containers);
+        }
+
+        stacks
+            .iter()
+            .filter_map(|stack| stack.last())
+            .map(|&krate| krate as char)
+            .collect()
     }
 
     fn count_tallest_part2(&self) -> usize {
@@ -264,3 +362,101 @@ impl Renderer {
         self.tick(move |this, count, from, to| this.move_crates(count, from, to, callback))
     }
 }
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 500;
+
+    /// Nine stacks of crates, and `size` steps moving them around.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let mut stacks = (0..9)
+            .map(|_| {
+                (0..rng.gen_range(1..=8))
+                    .map(|_| rng.gen_range(b'A'..=b'Z'))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let tallest = stacks.iter().map(Vec::len).max().unwrap();
+
+        let mut input = String::new();
+        for height in (0..tallest).rev() {
+            let row = stacks
+                .iter()
+                .map(|stack| match stack.get(height) {
+                    Some(&krate) => format!("[{}]", krate as char),
+                    None => "   ".to_owned(),
+                })
+                .join(" ");
+            input += &row;
+            input.push('\n');
+        }
+        input += &(1..=stacks.len()).map(|i| format!(" {} ", i)).join(" ");
+        input += "\n\n";
+
+        for _ in 0..size {
+            let from = loop {
+                let from = rng.gen_range(0..stacks.len());
+                if !stacks[from].is_empty() {
+                    break from;
+                }
+            };
+            let to = (from + rng.gen_range(1..stacks.len())) % stacks.len();
+            let count = rng.gen_range(1..=stacks[from].len());
+
+            let from_stack = &mut stacks[from];
+            let mut moving = from_stack.split_off(from_stack.len() - count);
+            stacks[to].append(&mut moving);
+            input += &format!("move {} from {} to {}\n", count, from + 1, to + 1);
+        }
+
+        input
+    }
+}
+
+// the slow way: really does lift the crates one at a time, and for the CrateMover 9001 sets them
+// down on the side first, so they go back up in the order they came off
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        fn crane(&self, set_aside: bool) -> String {
+            let mut stacks = self.initial.clone();
+
+            for &(count, from, to) in &self.steps {
+                let mut aside = vec![];
+                for _ in 0..count {
+                    let krate = stacks[from - 1].pop().unwrap();
+                    if set_aside {
+                        aside.push(krate);
+                    } else {
+                        stacks[to - 1].push(krate);
+                    }
+                }
+                while let Some(krate) = aside.pop() {
+                    stacks[to - 1].push(krate);
+                }
+            }
+
+            stacks
+                .iter()
+                .filter_map(|stack| stack.last())
+                .map(|&krate| krate as char)
+                .collect()
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<String, Infallible> {
+            Ok(self.crane(false))
+        }
+
+        fn reference_part2(&self) -> Result<String, Infallible> {
+            Ok(self.crane(true))
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(30);
+prelude::parser_tests!(30);
+prelude::differential_tests!(30);
@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+
+use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
+use prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 7;
+pub const EXAMPLE_PART2: usize = 19;
+
+/// How many characters in a row have to all be different to mark the start of a packet (part 1)
+/// or a message (part 2).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub packet_marker: usize,
+    pub message_marker: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            packet_marker: 4,
+            message_marker: 14,
+        }
+    }
+}
+
+/// Looks for the first `window` characters in a row that are all different, in a datastream that
+/// can be fed to it a piece at a time, as it arrives.  Each character is looked at once, no matter
+/// how big the window is.
+#[derive(Debug, Clone)]
+pub struct MarkerFinder {
+    window: usize,
+    // the last `window` characters, and how many times each one is in there
+    recent: VecDeque<u8>,
+    counts: [usize; 256],
+    // how many of `counts` aren't zero
+    distinct: usize,
+    received: usize,
+    marker: Option<usize>,
+}
+
+impl MarkerFinder {
+    pub fn new(window: usize) -> Self {
+        MarkerFinder {
+            window,
+            // the window comes from the config, so it grows as characters come in instead of
+            // setting aside room for however big that says
+            recent: VecDeque::new(),
+            counts: [0; 256],
+            distinct: 0,
+            received: 0,
+            // nothing at all is as different as it needs to be
+            marker: (window == 0).then_some(0),
+        }
+    }
+
+    /// Takes in the next part of the datastream, and returns how many characters had been received
+    /// by the end of the marker, if there's been one yet.  Anything after the marker is ignored.
+    pub fn feed(&mut self, chunk: &[u8]) -> Option<usize> {
+        for &c in chunk {
+            if self.marker.is_some() {
+                break;
+            }
+            self.received += 1;
+
+            self.recent.push_back(c);
+            self.counts[c as usize] += 1;
+            if self.counts[c as usize] == 1 {
+                self.distinct += 1;
+            }
+
+            if self.recent.len() > self.window {
+                let old = self.recent.pop_front().unwrap();
+                self.counts[old as usize] -= 1;
+                if self.counts[old as usize] == 0 {
+                    self.distinct -= 1;
+                }
+            }
+
+            if self.distinct == self.window {
+                self.marker = Some(self.received);
+            }
+        }
+
+        self.marker
+    }
+
+    /// How many characters had been received by the end of the marker, if it's been found.
+    pub fn marker(&self) -> Option<usize> {
+        self.marker
+    }
+
+    /// How many characters have been looked at, which stops counting at the marker.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+}
+
+#[derive(Serialize)]
+pub struct Solution {
+    datastream: String,
+    #[serde(skip)]
+    config: Config,
+}
+
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
+        init();
+
+        // the whole datastream is on the first line
+        let mut lines = input.lines();
+        let datastream = lines.next().unwrap_or("");
+        if let Some(position) = datastream.find(|c: char| !c.is_ascii_lowercase()) {
+            return Err(ParseError::at(
+                1,
+                datastream,
+                &datastream[position..],
+                "a lowercase letter",
+            ));
+        }
+        if let Some((i, line)) = lines.enumerate().find(|(_, line)| !line.is_empty()) {
+            return Err(ParseError::at(i + 2, line, line, "end of input"));
+        }
+
+        debug!("parsed {} characters", datastream.len());
+
+        Ok(Solution {
+            datastream: datastream.to_owned(),
+            config,
+        })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = usize;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
+
+    fn part1(&self) -> Result<usize, String> {
+        self.find_marker(self.config.packet_marker)
+    }
+
+    fn part2(&self) -> Result<usize, String> {
+        self.find_marker(self.config.message_marker)
+    }
+}
+
+impl Solution {
+    fn find_marker(&self, window: usize) -> Result<usize, String> {
+        MarkerFinder::new(window)
+            .feed(self.datastream.as_bytes())
+            .ok_or_else(|| format!("no {} characters in a row are all different", window))
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 4096;
+
+    /// A datastream `size` characters long (and at least as long as a message marker), with only
+    /// one message marker planted in it somewhere, among letters that repeat too often to make
+    /// another.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let marker = Config::default().message_marker;
+        let size = size.max(marker);
+        let mut letters = ('a'..='z').collect::<Vec<_>>();
+        letters.shuffle(rng);
+
+        // no window of this few letters can ever be a message marker
+        let (common, _) = letters.split_at(marker - 1);
+        let mut datastream = (0..size)
+            .map(|_| *common.choose(rng).unwrap())
+            .collect::<Vec<_>>();
+
+        letters.shuffle(rng);
+        let start = rng.gen_range(0..=size - marker);
+        datastream[start..start + marker].copy_from_slice(&letters[..marker]);
+
+        datastream.into_iter().chain(['\n']).collect()
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(100);
+prelude::parser_tests!(100);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn more_examples() {
+        for (datastream, packet, message) in [
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ] {
+            let solution = Solution::new(datastream).unwrap();
+            assert_eq!(solution.part1(), Ok(packet), "{}", datastream);
+            assert_eq!(solution.part2(), Ok(message), "{}", datastream);
+        }
+    }
+
+    #[test]
+    fn no_marker() {
+        let solution = Solution::new("abcabcabc").unwrap();
+        assert!(solution.part1().is_err());
+    }
+
+    #[test]
+    fn huge_window() {
+        for window in [usize::MAX / 2, usize::MAX] {
+            let mut finder = MarkerFinder::new(window);
+            assert_eq!(finder.feed(b"abcdefghijklmnopqrstuvwxyz"), None);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn chunks_dont_matter(
+            datastream in "[a-f]{0,60}",
+            window in 0..8usize,
+            cuts in prop::collection::vec(0..60usize, 0..6),
+        ) {
+            let datastream = datastream.as_bytes();
+            let mut cuts = cuts.into_iter().filter(|&i| i <= datastream.len()).collect::<Vec<_>>();
+            cuts.push(0);
+            cuts.push(datastream.len());
+            cuts.sort();
+
+            let mut finder = MarkerFinder::new(window);
+            for (start, end) in cuts.iter().zip(&cuts[1..]) {
+                finder.feed(&datastream[*start..*end]);
+            }
+
+            // the first place where the last `window` characters are all different
+            let expected = (window..=datastream.len()).find(|&end| {
+                datastream[end - window..end].iter().collect::<HashSet<_>>().len() == window
+            });
+            prop_assert_eq!(finder.marker(), expected);
+        }
+    }
+}
@@ -1,21 +1,27 @@
 use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
 use prelude::*;
-use wasm_bindgen::JsValue;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 95437;
+pub const EXAMPLE_PART2: u64 = 24933642;
+
+#[derive(Debug, Serialize)]
 enum Command {
     Cd(Component),
     Ls(Vec<InputEntry>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 enum Component {
     Root,
     Parent,
     Path(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 enum InputEntry {
     File { size: u32, name: String },
     Directory(String),
@@ -24,33 +30,60 @@ enum InputEntry {
 use Command::*;
 use Component::*;
 
+/// The sizes the puzzle talks about, in the same units as the files' sizes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Part 1 adds up the directories at most this big.
+    pub small_directory: u64,
+    pub disk_size: u64,
+    /// Part 2 looks for a directory to delete to leave this much free space.
+    pub update_size: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            small_directory: 100_000,
+            disk_size: 70_000_000,
+            update_size: 30_000_000,
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct Solution {
     commands: Vec<Command>,
+    #[serde(skip)]
+    config: Config,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
         init();
 
         let mut commands = vec![];
-        let mut lines = input.lines().peekable();
+        let mut lines = input.lines().enumerate().peekable();
 
-        while let Some(line) = lines.next() {
+        while let Some((i, line)) = lines.next() {
             let command = match line {
                 "$ cd /" => Cd(Root),
                 "$ cd .." => Cd(Parent),
                 "$ ls" => {
                     let mut entries = vec![];
-                    while let Some(entry) = lines.next_if(|&x| !x.starts_with("$")) {
+                    while let Some((i, entry)) = lines.next_if(|&(_, x)| !x.starts_with("$")) {
                         if entry.starts_with("dir ") {
                             entries.push(InputEntry::Directory(
                                 entry.strip_prefix("dir ").unwrap().to_owned(),
                             ))
                         } else {
-                            let (size, name) =
-                                entry.split_once(' ').expect("there should be a space");
+                            let (size, name) = entry
+                                .split_once(' ')
+                                .ok_or_else(|| ParseError::end_of_line(i + 1, entry, "a space"))?;
                             entries.push(InputEntry::File {
-                                size: size.parse().expect("couldn't parse size"),
+                                size: parse_word(i + 1, entry, size, "a size or \"dir\"")?,
                                 name: name.to_owned(),
                             })
                         }
@@ -60,7 +93,7 @@ impl Solution {
                 x if x.starts_with("$ cd ") => {
                     Cd(Path(x.strip_prefix("$ cd ").unwrap().to_owned()))
                 }
-                x => panic!("unparsed line {:?}", x),
+                x => return Err(ParseError::at(i + 1, x, x, "a cd or ls command")),
             };
 
             commands.push(command);
@@ -68,10 +101,20 @@ impl Solution {
 
         debug! {"parsed: {:#?}", commands}
 
-        Self { commands }
+        Ok(Self { commands, config })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = u64;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
     }
 
-    pub fn part1(&self) -> Result<u64, JsValue> {
+    fn part1(&self) -> Result<u64, String> {
         let tree = build_tree(self.commands.iter())?;
         debug!("{:#?}", tree);
 
@@ -79,7 +122,7 @@ impl Solution {
 
         recurse_through(&tree, &mut |this_level| {
             // part 1's condition seems to allow subtrees to be double-counted, so
-            if this_level <= 100_000 {
+            if this_level <= self.config.small_directory {
                 sum += this_level;
             }
         });
@@ -87,13 +130,22 @@ impl Solution {
         Ok(sum)
     }
 
-    pub fn part2(&self) -> Result<u64, JsValue> {
+    fn part2(&self) -> Result<u64, String> {
         let tree = build_tree(self.commands.iter())?;
 
         let total = recurse_through(&tree, &mut |_| ());
         debug!("total size is {}", total);
-        assert!(total > 40000000);
-        let needed = total - 40000000;
+        let most_used = self
+            .config
+            .disk_size
+            .saturating_sub(self.config.update_size);
+        if total <= most_used {
+            return Err(format!(
+                "{} is used, which already leaves enough space for the update",
+                total
+            ));
+        }
+        let needed = total - most_used;
         debug!("needed: {}", needed);
 
         let mut smallest_directory_that_is_big_enough = u64::MAX;
@@ -121,7 +173,7 @@ enum UpwardCd {
     Root,
 }
 
-fn build_tree<'a, I>(commands: I) -> Result<Tree<'a>, JsValue>
+fn build_tree<'a, I>(commands: I) -> Result<Tree<'a>, String>
 where
     I: Iterator<Item = &'a Command>,
 {
@@ -137,7 +189,7 @@ where
     Ok(tree)
 }
 
-fn build_level<'a, I>(commands: &mut I, tree: &mut Tree<'a>) -> Result<UpwardCd, JsValue>
+fn build_level<'a, I>(commands: &mut I, tree: &mut Tree<'a>) -> Result<UpwardCd, String>
 where
     I: Iterator<Item = &'a Command>,
 {
@@ -162,7 +214,7 @@ where
                         }
                     }
                     TreeEntry::File(_) => {
-                        return Err(format!("tried to chdir into a file: {:?}", p).into())
+                        return Err(format!("tried to chdir into a file: {:?}", p))
                     }
                 }
             }
@@ -175,7 +227,7 @@ where
                         InputEntry::Directory(name) => {
                             // put a new empty subtree in its place, if one didn't already exist
                             let TreeEntry::Directory(_) = tree.entry(name.as_str()).or_insert_with(|| TreeEntry::Directory(Tree::new())) else {
-                                return Err(format!("tried to replace a file with a directory: {:?}", name).into());
+                                return Err(format!("tried to replace a file with a directory: {:?}", name));
                             };
                         }
                     }
@@ -203,3 +255,204 @@ where
 
     this_level
 }
+
+// a directory made up by the generator: its files' names and sizes, and its subdirectories' names
+// and indexes
+#[derive(Default)]
+struct GeneratedDirectory {
+    files: Vec<(String, u32)>,
+    directories: Vec<(String, usize)>,
+    taken: HashSet<String>,
+}
+
+impl GeneratedDirectory {
+    // a name that's not in this directory yet, like "qzbnf" or "qzbnf.lpt"
+    fn new_name(&mut self, rng: &mut impl Rng, extension: bool) -> String {
+        fn word(rng: &mut impl Rng) -> String {
+            (0..rng.gen_range(1..=8))
+                .map(|_| rng.gen_range('a'..='z'))
+                .collect()
+        }
+
+        loop {
+            let mut name = word(rng);
+            if extension && rng.gen_bool(0.5) {
+                name = format!("{}.{}", name, word(rng));
+            }
+            if self.taken.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+}
+
+// everything `ls` and `cd` would show while walking the whole tree, starting from `index`
+fn walk(directories: &[GeneratedDirectory], index: usize, rng: &mut impl Rng, output: &mut String) {
+    let directory = &directories[index];
+    let mut entries = directory
+        .files
+        .iter()
+        .map(|(name, size)| format!("{} {}", size, name))
+        .chain(
+            directory
+                .directories
+                .iter()
+                .map(|(name, _)| format!("dir {}", name)),
+        )
+        .collect_vec();
+    entries.shuffle(rng);
+
+    output.push_str("$ ls\n");
+    for entry in entries {
+        output.push_str(&entry);
+        output.push('\n');
+    }
+    for (name, subdirectory) in &directory.directories {
+        output.push_str(&format!("$ cd {}\n", name));
+        walk(directories, *subdirectory, rng, output);
+        output.push_str("$ cd ..\n");
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 200;
+
+    /// A filesystem with `size` directories, full enough that part 2 has to delete one of them.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let mut directories = vec![GeneratedDirectory::default()];
+        for index in 1..size.max(1) {
+            let parent = rng.gen_range(0..index);
+            let name = directories[parent].new_name(rng, false);
+            directories[parent].directories.push((name, index));
+            directories.push(GeneratedDirectory::default());
+        }
+
+        let mut total = 0;
+        for directory in &mut directories {
+            for _ in 0..rng.gen_range(0..=4) {
+                let name = directory.new_name(rng, true);
+                let size = rng.gen_range(1..=300_000);
+                total += size;
+                directory.files.push((name, size));
+            }
+        }
+
+        // the default config's update needs more than 40,000,000 to already be used
+        let config = Config::default();
+        let most_used = (config.disk_size - config.update_size) as u32;
+        if total <= most_used {
+            let directory = directories.choose_mut(rng).unwrap();
+            let name = directory.new_name(rng, true);
+            let size = most_used - total + rng.gen_range(1..=config.update_size as u32 / 2);
+            directory.files.push((name, size));
+        }
+
+        let mut input = "$ cd /\n".to_owned();
+        walk(&directories, 0, rng, &mut input);
+        input
+    }
+}
+
+// the slow way: keeps every file by its whole path instead of building a tree, and sizes up each
+// directory by going through all the files to find the ones under it
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        // the size of every directory, the root first
+        fn directory_sizes(&self) -> Result<Vec<u64>, String> {
+            let mut cwd: Vec<&str> = vec![];
+            let mut files: HashMap<Vec<&str>, u32> = HashMap::new();
+            let mut directories: Vec<Vec<&str>> = vec![vec![]];
+
+            for command in &self.commands {
+                match command {
+                    Cd(Root) => cwd.clear(),
+                    Cd(Parent) => {
+                        cwd.pop();
+                    }
+                    Cd(Path(name)) => {
+                        cwd.push(name);
+                        if files.contains_key(&cwd) {
+                            return Err(format!("tried to chdir into a file: {:?}", name));
+                        }
+                        if !directories.contains(&cwd) {
+                            return Err(format!(
+                                "tried to chdir to {:?} before it was seen by an ls",
+                                name
+                            ));
+                        }
+                    }
+                    Ls(entries) => {
+                        for entry in entries {
+                            match entry {
+                                InputEntry::File { size, name } => {
+                                    let path = [cwd.as_slice(), &[name.as_str()]].concat();
+                                    // a file in place of a directory takes everything in it along
+                                    files.retain(|file, _| !file.starts_with(&path));
+                                    directories.retain(|directory| !directory.starts_with(&path));
+                                    files.insert(path, *size);
+                                }
+                                InputEntry::Directory(name) => {
+                                    let path = [cwd.as_slice(), &[name.as_str()]].concat();
+                                    if files.contains_key(&path) {
+                                        return Err(format!(
+                                            "tried to replace a file with a directory: {:?}",
+                                            name
+                                        ));
+                                    }
+                                    if !directories.contains(&path) {
+                                        directories.push(path);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            Ok(directories
+                .iter()
+                .map(|directory| {
+                    files
+                        .iter()
+                        .filter(|(file, _)| file.starts_with(directory))
+                        .map(|(_, &size)| size as u64)
+                        .sum()
+                })
+                .collect())
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u64, String> {
+            Ok(self
+                .directory_sizes()?
+                .into_iter()
+                .filter(|&size| size <= self.config.small_directory)
+                .sum())
+        }
+
+        fn reference_part2(&self) -> Result<u64, String> {
+            let sizes = self.directory_sizes()?;
+            let free = self.config.disk_size as i64 - sizes[0] as i64;
+            if free >= self.config.update_size as i64 {
+                return Err(format!(
+                    "{} is used, which already leaves enough space for the update",
+                    sizes[0]
+                ));
+            }
+            Ok(sizes
+                .into_iter()
+                .filter(|&size| free + size as i64 >= self.config.update_size as i64)
+                .min()
+                .unwrap())
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+prelude::differential_tests!(20);
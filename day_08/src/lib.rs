@@ -0,0 +1,191 @@
+use std::convert::Infallible;
+
+use prelude::log::debug;
+use prelude::rand::Rng;
+use prelude::*;
+use serde::Serialize;
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 21;
+pub const EXAMPLE_PART2: u64 = 8;
+
+/// What one tree looks like from the edges of the forest, and what can be seen from its tree house.
+/// Each array is in the order of [`Direction::ALL`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tree {
+    pub height: u8,
+    /// Whether it can be seen from outside the forest, looking from that edge.
+    pub visible_from: [bool; 4],
+    /// How many trees can be seen looking that way, up to and including the first one at least
+    /// as tall as this one.
+    pub viewing_distances: [u32; 4],
+}
+
+impl Tree {
+    pub fn is_visible(&self) -> bool {
+        self.visible_from.iter().any(|&visible| visible)
+    }
+
+    pub fn scenic_score(&self) -> u64 {
+        self.viewing_distances
+            .iter()
+            .map(|&distance| distance as u64)
+            .product()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Solution {
+    heights: Grid<u8>,
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = u64;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        init();
+
+        let heights = Grid::parse(input, "a height from 0 to 9", |c| {
+            c.to_digit(10).map(|height| height as u8)
+        })?;
+        debug!("parsed:\n{}", heights);
+
+        Ok(Solution { heights })
+    }
+
+    fn part1(&self) -> Result<usize, Infallible> {
+        Ok(self
+            .visibility()
+            .iter()
+            .filter(|&(_, &visible)| visible)
+            .count())
+    }
+
+    fn part2(&self) -> Result<u64, Infallible> {
+        Ok(self
+            .scenic_scores()
+            .iter()
+            .map(|(_, &score)| score)
+            .max()
+            .unwrap_or(0))
+    }
+}
+
+impl Solution {
+    /// Every tree in the forest, looked at from every direction.
+    pub fn survey(&self) -> Grid<Tree> {
+        let mut trees = self.heights.map(|&height| Tree {
+            height,
+            visible_from: [false; 4],
+            viewing_distances: [0; 4],
+        });
+
+        for ((row, column), &height) in self.heights.iter() {
+            for (i, direction) in Direction::ALL.into_iter().enumerate() {
+                let mut visible = true;
+                let mut distance = 0;
+                for other in self.line_of_sight((row, column), direction) {
+                    distance += 1;
+                    if self.heights[other] >= height {
+                        visible = false;
+                        break;
+                    }
+                }
+
+                let tree = &mut trees[(row, column)];
+                tree.visible_from[i] = visible;
+                tree.viewing_distances[i] = distance;
+            }
+        }
+
+        trees
+    }
+
+    /// Whether each tree can be seen from outside the forest.
+    pub fn visibility(&self) -> Grid<bool> {
+        self.survey().map(Tree::is_visible)
+    }
+
+    /// Each tree's scenic score.
+    pub fn scenic_scores(&self) -> Grid<u64> {
+        self.survey().map(Tree::scenic_score)
+    }
+
+    // every tree from `position` (but not including it) to the edge, going `direction`
+    fn line_of_sight(
+        &self,
+        position: (usize, usize),
+        direction: Direction,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let offset = direction.offset();
+        std::iter::successors(Some(position), move |&(row, column)| {
+            let next = (
+                row.checked_add_signed(offset.y as isize)?,
+                column.checked_add_signed(offset.x as isize)?,
+            );
+            self.heights.get(next).map(|_| next)
+        })
+        .skip(1)
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 99;
+
+    /// A square forest `size` trees on a side.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let size = size.max(1);
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| char::from(b'0' + rng.gen_range(0..=9)))
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect()
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_maps() {
+        let solution = Solution::new(EXAMPLE).unwrap();
+
+        // everything on the edge, and five trees in the middle
+        assert_eq!(
+            solution
+                .visibility()
+                .render(|&visible| if visible { '#' } else { '.' }),
+            "#####\n###.#\n##.##\n#.#.#\n#####\n"
+        );
+
+        let trees = solution.survey();
+        // the puzzle's two trees: the 5 in the middle of the second row, and the one in the
+        // middle of the fourth
+        assert_eq!(trees[(1, 2)].viewing_distances, [1, 2, 2, 1]);
+        assert_eq!(trees[(1, 2)].scenic_score(), 4);
+        assert_eq!(trees[(3, 2)].viewing_distances, [2, 2, 1, 2]);
+        assert_eq!(trees[(3, 2)].scenic_score(), 8);
+        assert_eq!(trees[(3, 2)].visible_from, [false, false, true, true]);
+    }
+
+    #[test]
+    fn wide_open() {
+        // a tall tree in the middle of a flat forest can see 300 trees every way, and 300^4 is more
+        // than a u32 holds
+        let mut input = ("0".repeat(601) + "\n").repeat(601);
+        let middle = 300 * 602 + 300;
+        input.replace_range(middle..=middle, "9");
+        let solution = Solution::new(&input).unwrap();
+        assert_eq!(solution.part2().unwrap(), 300u64.pow(4));
+    }
+}
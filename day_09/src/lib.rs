@@ -1,10 +1,17 @@
+use std::convert::Infallible;
+
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
-use wasm_bindgen::JsValue;
+use serde::{Deserialize, Serialize};
 
 use Move::*;
 
-#[derive(Debug)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 13;
+pub const EXAMPLE_PART2: usize = 1;
+
+#[derive(Debug, Serialize)]
 enum Move {
     Up(i32),
     Down(i32),
@@ -12,116 +19,256 @@ enum Move {
     Right(i32),
 }
 
-impl TryFrom<&str> for Move {
-    type Error = String;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match &value.split(' ').collect_vec()[..] {
-            &[direction, count] => {
-                let count = count
-                    .parse()
-                    .map_err(|e| format!("couldn't parse count: {:?}", e))?;
-                match direction {
-                    "U" => Ok(Up(count)),
-                    "D" => Ok(Down(count)),
-                    "L" => Ok(Left(count)),
-                    "R" => Ok(Right(count)),
-                    x => Err(format!("unexpected direction: {:?}", x)),
-                }
-            }
-            v => Err(format!("expected a direction and a count, got {:?}", v)),
+impl Move {
+    fn parse(line_number: usize, line: &str) -> Result<Self, ParseError> {
+        let Some((direction, count)) = line.split_once(' ') else {
+            return Err(ParseError::end_of_line(
+                line_number,
+                line,
+                "a direction and a count",
+            ));
+        };
+        let count = parse_word(line_number, line, count, "a count")?;
+        match direction {
+            "U" => Ok(Up(count)),
+            "D" => Ok(Down(count)),
+            "L" => Ok(Left(count)),
+            "R" => Ok(Right(count)),
+            x => Err(ParseError::at(line_number, line, x, "U, D, L, or R")),
         }
     }
+
+    fn direction_and_count(&self) -> (Direction, i32) {
+        match *self {
+            Up(x) => (Direction::North, x),
+            Down(x) => (Direction::South, x),
+            Left(x) => (Direction::West, x),
+            Right(x) => (Direction::East, x),
+        }
+    }
+}
+
+/// How long the rope is in each part, counting the head and the tail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub part1_knots: usize,
+    pub part2_knots: usize,
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            part1_knots: 2,
+            part2_knots: 10,
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct Solution {
     directions: Vec<Move>,
+    #[serde(skip)]
+    config: Config,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Result<Solution, JsValue> {
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
         init();
 
         let directions = input
             .lines()
-            .map(Move::try_from)
+            .enumerate()
+            .map(|(i, line)| Move::parse(i + 1, line))
             .collect::<Result<Vec<_>, _>>()?;
 
         debug!("parsed: {:#?}", directions);
 
-        Ok(Solution { directions })
+        Ok(Solution { directions, config })
     }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = usize;
+    type Error = Infallible;
 
-    pub fn part1(&self) -> usize {
-        self.simulate(2)
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
     }
 
-    pub fn part2(&self) -> usize {
-        self.simulate(10)
+    fn part1(&self) -> Result<usize, Infallible> {
+        Ok(self.simulate(self.config.part1_knots))
+    }
+
+    fn part2(&self) -> Result<usize, Infallible> {
+        Ok(self.simulate(self.config.part2_knots))
+    }
+}
+
+impl Solution {
+    /// The rope, with `knots` knots all starting at the origin, before its head has moved.
+    pub fn rope(&self, knots: usize) -> RopeSimulation<'_> {
+        let mut simulation = RopeSimulation {
+            directions: &self.directions,
+            next: 0,
+            moved: 0,
+            rope: Rope {
+                // there's always at least a head
+                knots: vec![Point2::default(); knots.max(1)],
+                visited: HashSet::new(),
+            },
+        };
+        simulation.skip_finished();
+        simulation
     }
 
     fn simulate(&self, knots: usize) -> usize {
-        let mut positions = vec![(0_i32, 0_i32); knots];
-        let mut visited = HashSet::new();
-
-        for instruction in &self.directions {
-            let (dx, dy, &count) = match instruction {
-                Up(x) => (0, 1, x),
-                Down(x) => (0, -1, x),
-                Left(x) => (-1, 0, x),
-                Right(x) => (1, 0, x),
-            };
-
-            for _ in 0..count {
-                positions[0] = (positions[0].0 + dx, positions[0].1 + dy);
-
-                // reconcile the position of each knot ('tail') to follow the knot in front of it
-                // ('head')
-                for tail_idx in 1..positions.len() {
-                    let (head, tail) = positions.split_at_mut(tail_idx);
-                    let head = head.last_mut().unwrap();
-                    let tail = tail.first_mut().unwrap();
-
-                    if head.0 == tail.0 {
-                        // same row
-                        if head.1 > tail.1 + 1 {
-                            tail.1 += 1;
-                        } else if head.1 < tail.1 - 1 {
-                            tail.1 -= 1;
-                        }
-                    } else if head.1 == tail.1 {
-                        // same column
-                        if head.0 > tail.0 + 1 {
-                            tail.0 += 1;
-                        } else if head.0 < tail.0 - 1 {
-                            tail.0 -= 1;
-                        }
-                    } else {
-                        // something diagonal may happen
-                        if (head.0 - tail.0).abs() > 1 || (head.1 - tail.1).abs() > 1 {
-                            // they're not touching, so tail needs to move diagonally
-
-                            // we know they're not in the same row or column if we get here, so these
-                            // will always move tail left-or-right *and* up-or-down.
-                            if head.0 > tail.0 {
-                                tail.0 += 1;
-                            } else {
-                                tail.0 -= 1;
-                            }
-
-                            if head.1 > tail.1 {
-                                tail.1 += 1;
-                            } else {
-                                tail.1 -= 1;
-                            }
+        let mut rope = self.rope(knots);
+        rope.run();
+        rope.state().visited.len()
+    }
+}
+
+/// Where each knot of the rope is, starting from the head, and everywhere its tail has been.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    pub knots: Vec<Point2>,
+    pub visited: HashSet<Point2>,
+}
+
+/// Moves the head of a [`Rope`] one square per step, following the instructions.
+pub struct RopeSimulation<'a> {
+    directions: &'a [Move],
+    // the instruction being followed, and how many times the head has moved because of it
+    next: usize,
+    moved: i32,
+    rope: Rope,
+}
+
+impl RopeSimulation<'_> {
+    // moves on past any instructions that are done, including the ones that never moved at all
+    fn skip_finished(&mut self) {
+        while self
+            .directions
+            .get(self.next)
+            .is_some_and(|instruction| self.moved >= instruction.direction_and_count().1)
+        {
+            self.next += 1;
+            self.moved = 0;
+        }
+    }
+}
+
+impl Simulation for RopeSimulation<'_> {
+    type State = Rope;
+
+    fn step(&mut self) {
+        let Some(instruction) = self.directions.get(self.next) else {
+            return;
+        };
+        let (direction, _) = instruction.direction_and_count();
+        let positions = &mut self.rope.knots;
+
+        positions[0] += direction.offset();
+
+        // reconcile the position of each knot ('tail') to follow the knot in front of it ('head')
+        for tail_idx in 1..positions.len() {
+            let head = positions[tail_idx - 1];
+            let tail = &mut positions[tail_idx];
+
+            // once they're not touching, the tail takes one step toward the head: straight along a
+            // row or column if it's in one, or else diagonally
+            if head.chebyshev(*tail) > 1 {
+                *tail += (head - *tail).signum();
+            }
+        }
+
+        self.rope.visited.insert(*positions.last().unwrap());
+
+        self.moved += 1;
+        self.skip_finished();
+    }
+
+    fn state(&self) -> &Rope {
+        &self.rope
+    }
+
+    fn is_done(&self) -> bool {
+        self.next == self.directions.len()
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 2000;
+
+    /// `size` moves of the head.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{} {}\n",
+                    ['U', 'D', 'L', 'R'][rng.gen_range(0..4)],
+                    rng.gen_range(1..=20)
+                )
+            })
+            .collect()
+    }
+}
+
+// the slow way: when a knot falls behind, it looks at all nine squares it could be in next and
+// takes whichever is nearest the knot ahead of it, instead of working out which way to step
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    fn distance_squared(a: Point2, b: Point2) -> i32 {
+        let d = a - b;
+        d.x * d.x + d.y * d.y
+    }
+
+    impl Solution {
+        fn follow(&self, knots: usize) -> usize {
+            let mut rope = vec![Point2::default(); knots.max(1)];
+            let mut visited = HashSet::new();
+
+            for instruction in &self.directions {
+                let (direction, count) = instruction.direction_and_count();
+                for _ in 0..count {
+                    rope[0] += direction.offset();
+                    for i in 1..rope.len() {
+                        let (ahead, knot) = (rope[i - 1], rope[i]);
+                        if (ahead.x - knot.x).abs() <= 1 && (ahead.y - knot.y).abs() <= 1 {
+                            continue;
                         }
+                        rope[i] = (-1..=1)
+                            .cartesian_product(-1..=1)
+                            .map(|(dx, dy)| knot + Point2::new(dx, dy))
+                            .min_by_key(|&next| distance_squared(next, ahead))
+                            .unwrap();
                     }
+                    visited.insert(*rope.last().unwrap());
                 }
-
-                visited.insert(*positions.last().unwrap());
             }
+
+            visited.len()
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<usize, Infallible> {
+            Ok(self.follow(self.config.part1_knots))
         }
 
-        visited.len()
+        fn reference_part2(&self) -> Result<usize, Infallible> {
+            Ok(self.follow(self.config.part2_knots))
+        }
     }
 }
+
+prelude::example_tests!();
+prelude::generator_tests!(50);
+prelude::parser_tests!(50);
+prelude::differential_tests!(50);
@@ -1,39 +1,40 @@
+use std::convert::Infallible;
 use std::fmt::Debug;
 
-use js_sys::Function;
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
-use wasm_bindgen::prelude::*;
+use serde::Serialize;
 
 use Instruction::*;
 
-#[derive(Debug)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i64 = 13140;
+pub const EXAMPLE_PART2: &str = "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+#[derive(Debug, Serialize)]
 enum Instruction {
     Noop,
     Addx(i64),
 }
 
-impl TryFrom<&str> for Instruction {
-    type Error = String;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value == "noop" {
+impl Instruction {
+    fn parse(line_number: usize, line: &str) -> Result<Self, ParseError> {
+        if line == "noop" {
             Ok(Noop)
-        } else if let Some(num) = value.strip_prefix("addx ") {
-            let operand = num
-                .parse()
-                .map_err(|e| format!("error parsing operand: {:?}", e))?;
-            Ok(Addx(operand))
+        } else if let Some(num) = line.strip_prefix("addx ") {
+            Ok(Addx(parse_word(line_number, line, num, "an operand")?))
         } else {
-            Err(format!(
-                "expected noop or addx instruction, got {:?}",
-                value
-            ))
+            Err(ParseError::at(line_number, line, line, "noop or addx"))
         }
     }
-}
 
-impl Instruction {
     fn eval<F>(&self, x: i64, mut f: F) -> i64
     where
         F: FnMut(i64),
@@ -52,27 +53,30 @@ impl Instruction {
     }
 }
 
-#[wasm_bindgen]
+#[derive(Serialize)]
 pub struct Solution {
     instructions: Vec<Instruction>,
 }
 
-#[wasm_bindgen]
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = i64;
+    type Part2 = String;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
         let instructions = input
             .lines()
-            .map(Instruction::try_from)
-            .collect::<Result<_, _>>()
-            .unwrap();
+            .enumerate()
+            .map(|(i, line)| Instruction::parse(i + 1, line))
+            .collect::<Result<_, _>>()?;
         debug!("parsed: {:#?}", instructions);
 
-        Self { instructions }
+        Ok(Self { instructions })
     }
 
-    pub fn part1(&self) -> i64 {
+    fn part1(&self) -> Result<i64, Infallible> {
         let mut x = 1;
         let mut result = 0;
         let mut cycle = 0;
@@ -90,23 +94,39 @@ impl Solution {
 
         debug!("completed {} cycles", cycle);
 
-        result
+        Ok(result)
     }
 
-    pub fn part2(&self, light: Function) {
+    fn part2(&self) -> Result<String, Infallible> {
+        let mut screen = vec![vec!['.'; 40]; 6];
+
+        self.draw(|row, column| {
+            if screen.len() <= row {
+                screen.resize(row + 1, vec!['.'; 40]);
+            }
+            screen[row][column] = '#';
+        });
+
+        Ok(screen
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .join("\n"))
+    }
+}
+
+impl Solution {
+    /// Calls `light(row, column)` for every pixel that the CRT lights up, in the order it draws them.
+    pub fn draw<F>(&self, mut light: F)
+    where
+        F: FnMut(usize, usize),
+    {
         let mut x = 1;
         let mut cycle = 0;
 
         for instruction in &self.instructions {
             x = instruction.eval(x, |x| {
                 if ((x - 1)..=(x + 1)).contains(&(cycle % 40)) {
-                    light
-                        .call2(
-                            &JsValue::NULL,
-                            &JsValue::from_f64((cycle / 40) as f64),
-                            &JsValue::from_f64((cycle % 40) as f64),
-                        )
-                        .expect("light() call failed somehow");
+                    light((cycle / 40) as usize, (cycle % 40) as usize);
                 }
 
                 cycle += 1;
@@ -114,3 +134,80 @@ impl Solution {
         }
     }
 }
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 140;
+
+    /// `size` instructions.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                if rng.gen_bool(0.3) {
+                    "noop\n".to_owned()
+                } else {
+                    let value = rng.gen_range(1..=20);
+                    format!("addx {}\n", if rng.gen() { value } else { -value })
+                }
+            })
+            .collect()
+    }
+}
+
+// the slow way: writes down what X is during every cycle first, then reads the signal strengths
+// and the pixels off of that
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        // X during each cycle, starting from the first
+        fn history(&self) -> Vec<i64> {
+            let mut history = vec![];
+            let mut x = 1;
+            for instruction in &self.instructions {
+                match instruction {
+                    Noop => history.push(x),
+                    Addx(value) => {
+                        history.extend([x, x]);
+                        x += value;
+                    }
+                }
+            }
+            history
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i64, Infallible> {
+            let history = self.history();
+            Ok((20..=history.len())
+                .step_by(40)
+                .map(|cycle| cycle as i64 * history[cycle - 1])
+                .sum())
+        }
+
+        fn reference_part2(&self) -> Result<String, Infallible> {
+            let history = self.history();
+            let mut screen = (0..history.len().div_ceil(40).max(6))
+                .map(|row| {
+                    (0..40)
+                        .map(|column| match history.get(row * 40 + column) {
+                            Some(x) if (x - column as i64).abs() <= 1 => '#',
+                            _ => '.',
+                        })
+                        .collect::<String>()
+                })
+                .collect_vec();
+            // the screen is six rows tall, unless the program runs long enough to light up more
+            while screen.len() > 6 && !screen.last().unwrap().contains('#') {
+                screen.pop();
+            }
+            Ok(screen.join("\n"))
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(140);
+prelude::parser_tests!(140);
+prelude::differential_tests!(140);
@@ -1,7 +1,17 @@
+use std::fmt::{self, Display};
+use std::num::{NonZeroU32, NonZeroU64};
+
 use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 10605;
+pub const EXAMPLE_PART2: u64 = 2713310158;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 enum Operation {
     Square,
     Multiply(u32),
@@ -10,7 +20,7 @@ enum Operation {
 
 use Operation::*;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 struct Monkey {
     items: Vec<u32>,
     operation: Operation,
@@ -19,169 +29,573 @@ struct Monkey {
     false_target: usize,
 }
 
+/// How long the monkeys play for in each part, and how much calmer we get when they don't damage
+/// an item in part 1.  Getting no calmer at all is dividing by 1, not 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub part1_rounds: u32,
+    pub part2_rounds: u32,
+    pub part1_relief: NonZeroU64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            part1_rounds: 20,
+            part2_rounds: 10_000,
+            part1_relief: NonZeroU64::new(3).unwrap(),
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct Solution {
     monkeys: Vec<Monkey>,
+    #[serde(skip)]
+    config: Config,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+/// Writes the monkeys back out the way the input had them.
+impl Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "Monkey {}:", i)?;
+            writeln!(f, "  Starting items: {}", monkey.items.iter().join(", "))?;
+            match monkey.operation {
+                Square => writeln!(f, "  Operation: new = old * old")?,
+                Multiply(other) => writeln!(f, "  Operation: new = old * {}", other)?,
+                Add(other) => writeln!(f, "  Operation: new = old + {}", other)?,
+            }
+            writeln!(f, "  Test: divisible by {}", monkey.divisor)?;
+            writeln!(f, "    If true: throw to monkey {}", monkey.true_target)?;
+            writeln!(f, "    If false: throw to monkey {}", monkey.false_target)?;
+        }
+        Ok(())
+    }
+}
+
+// keeps track of the line number, so parse errors can point at where they happened
+struct Lines<'a> {
+    lines: std::str::Lines<'a>,
+    line_number: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        self.line_number += 1;
+        self.lines.next()
+    }
+
+    // returns the next line, and whatever came after `prefix` on it
+    fn field(&mut self, prefix: &str) -> Result<(&'a str, &'a str), ParseError> {
+        let expected = format!("{:?}", prefix.trim_start());
+        let Some(line) = self.next() else {
+            return Err(ParseError::end_of_input(self.line_number, expected));
+        };
+        let rest = line
+            .strip_prefix(prefix)
+            .ok_or_else(|| ParseError::at(self.line_number, line, line, expected))?;
+
+        Ok((line, rest))
+    }
+}
+
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
         init();
 
-        let mut lines = input.lines();
+        let mut lines = Lines {
+            lines: input.lines(),
+            line_number: 0,
+        };
         let mut monkeys = vec![];
+        // where each throw's target was written, to check once we know how many monkeys there are
+        let mut targets = vec![];
 
         while let Some(line) = lines.next() {
             debug!("line: {:?}", line);
 
             let monkey_id = line
                 .strip_prefix("Monkey ")
-                .expect("expected a monkey!")
-                .strip_suffix(':')
-                .unwrap()
-                .parse::<usize>()
-                .unwrap();
+                .and_then(|rest| rest.strip_suffix(':'))
+                .ok_or_else(|| ParseError::at(lines.line_number, line, line, "\"Monkey\""))?;
             // make sure we aren't given monkeys out of order
-            assert_eq!(monkey_id, monkeys.len());
+            if parse_word::<usize>(lines.line_number, line, monkey_id, "a monkey number")?
+                != monkeys.len()
+            {
+                return Err(ParseError::at(
+                    lines.line_number,
+                    line,
+                    monkey_id,
+                    format!("monkey {}", monkeys.len()),
+                ));
+            }
 
-            let items = lines
-                .next()
-                .expect("unexpected EOF")
-                .strip_prefix("  Starting items: ")
-                .unwrap()
+            let (line, items) = lines.field("  Starting items: ")?;
+            let items = items
                 .split(", ")
-                .map(|word| word.parse().unwrap())
-                .collect();
-
-            let operation_str = lines
-                .next()
-                .expect("unexpected EOF")
-                .strip_prefix("  Operation: new = old ")
-                .unwrap();
+                .map(|word| parse_word(lines.line_number, line, word, "a worry level"))
+                .collect::<Result<_, _>>()?;
+
+            let (line, operation_str) = lines.field("  Operation: new = old ")?;
             let operation = if operation_str == "* old" {
                 Square
             } else if let Some(other) = operation_str.strip_prefix("* ") {
-                let other = other.parse::<u32>().unwrap();
-                Multiply(other)
+                Multiply(parse_word(lines.line_number, line, other, "a number")?)
             } else if let Some(other) = operation_str.strip_prefix("+ ") {
-                let other = other.parse::<u32>().unwrap();
-                Add(other)
+                Add(parse_word(lines.line_number, line, other, "a number")?)
             } else {
-                panic!("could not parse operation {:?}", operation_str);
+                return Err(ParseError::at(
+                    lines.line_number,
+                    line,
+                    operation_str,
+                    "* or +",
+                ));
             };
 
-            let divisor = lines
-                .next()
-                .expect("unexpected EOF")
-                .strip_prefix("  Test: divisible by ")
-                .unwrap()
-                .parse()
-                .unwrap();
-
-            let true_target = lines
-                .next()
-                .expect("unexpected EOF")
-                .strip_prefix("    If true: throw to monkey ")
-                .unwrap()
-                .parse()
-                .unwrap();
-
-            let false_target = lines
-                .next()
-                .expect("unexpected EOF")
-                .strip_prefix("    If false: throw to monkey ")
-                .unwrap()
-                .parse()
-                .unwrap();
+            let (line, divisor) = lines.field("  Test: divisible by ")?;
+            let divisor = parse_word::<NonZeroU32>(lines.line_number, line, divisor, "a divisor")?;
+
+            let (line, word) = lines.field("    If true: throw to monkey ")?;
+            let true_target = parse_word(lines.line_number, line, word, "a monkey number")?;
+            targets.push((lines.line_number, line, word, true_target));
+
+            let (line, word) = lines.field("    If false: throw to monkey ")?;
+            let false_target = parse_word(lines.line_number, line, word, "a monkey number")?;
+            targets.push((lines.line_number, line, word, false_target));
 
             match lines.next() {
                 None => (),
                 Some("") => (),
-                Some(x) => panic!("expected empty line, got {:?}", x),
+                Some(x) => return Err(ParseError::at(lines.line_number, x, x, "an empty line")),
             }
 
             monkeys.push(Monkey {
                 items,
                 operation,
-                divisor,
+                divisor: divisor.get(),
                 true_target,
                 false_target,
             })
         }
+        if monkeys.is_empty() {
+            return Err(ParseError::end_of_input(
+                lines.line_number,
+                "at least one monkey",
+            ));
+        }
 
-        Solution { monkeys }
+        for (line_number, line, word, target) in targets {
+            if target >= monkeys.len() {
+                return Err(ParseError::at(
+                    line_number,
+                    line,
+                    word,
+                    format!("a monkey number less than {}", monkeys.len()),
+                ));
+            }
+        }
+
+        Ok(Solution { monkeys, config })
     }
+}
 
-    pub fn part1(&self) -> u32 {
-        let mut monkeys = self.monkeys.clone();
-        let mut activity = vec![0; monkeys.len()];
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = u64;
+    type Error = String;
 
-        for _round in 0..20 {
-            for i in 0..monkeys.len() {
-                for item in std::mem::take(&mut monkeys[i].items) {
-                    activity[i] += 1;
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
 
-                    let item = match monkeys[i].operation {
-                        Square => item * item,
-                        Multiply(other) => item * other,
-                        Add(other) => item + other,
-                    };
-                    let item = item / 3;
+    fn part1(&self) -> Result<u64, String> {
+        let mut keep_away = self.keep_away(Relief::DivideBy(self.config.part1_relief));
+        keep_away.run_for(self.config.part1_rounds as usize);
 
-                    let target = if item % monkeys[i].divisor == 0 {
-                        monkeys[i].true_target
-                    } else {
-                        monkeys[i].false_target
-                    };
+        keep_away.monkey_business()
+    }
 
-                    monkeys[target].items.push(item);
-                }
+    fn part2(&self) -> Result<u64, String> {
+        let mut keep_away = self.keep_away(Relief::None);
+
+        let rounds = self.config.part2_rounds;
+        for round in 0..rounds {
+            debug!("round {}", round);
+            progress::report(round.into(), Some(rounds.into()))
+                .map_err(|cancelled| cancelled.to_string())?;
+            keep_away.step();
+        }
+
+        keep_away.monkey_business()
+    }
+}
+
+impl Solution {
+    /// The monkeys' game, before anyone has thrown anything.
+    pub fn keep_away(&self, relief: Relief) -> KeepAway<'_> {
+        KeepAway {
+            monkeys: &self.monkeys,
+            relief,
+            // with no relief, we'll do all our math modulo the product of all the divisors, since
+            // they seem to be small primes (and if they aren't, it might not even fit in a u64).
+            //
+            // TODO: find the theorem that says we can do math modulo p1*p2*p3*...
+            modulus: self.monkeys.iter().try_fold(1u64, |product, monkey| {
+                product.checked_mul(monkey.divisor as u64)
+            }),
+            troop: Troop {
+                items: self
+                    .monkeys
+                    .iter()
+                    .map(|monkey| monkey.items.iter().map(|&item| item as u64).collect_vec())
+                    .collect_vec(),
+                inspected: vec![0; self.monkeys.len()],
+                rounds: 0,
+            },
+            overflowed: false,
+        }
+    }
+}
+
+/// What happens to the worry level of an item after a monkey inspects it and doesn't damage it.
+#[derive(Debug, Clone, Copy)]
+pub enum Relief {
+    DivideBy(NonZeroU64),
+    None,
+}
+
+/// The items each monkey is holding, and how many each has inspected so far.
+#[derive(Debug, Clone)]
+pub struct Troop {
+    pub items: Vec<Vec<u64>>,
+    pub inspected: Vec<u64>,
+    pub rounds: u32,
+}
+
+impl Troop {
+    /// The number of items inspected by the two most active monkeys, multiplied together.
+    pub fn monkey_business(&self) -> u64 {
+        let mut inspected = self.inspected.clone();
+        inspected.sort_by_key(|&i| std::cmp::Reverse(i));
+        inspected.iter().take(2).product()
+    }
+}
+
+/// Plays keep away one round at a time.  The monkeys never get bored of it, so it's only done if a
+/// worry level gets too big to keep track of.
+pub struct KeepAway<'a> {
+    monkeys: &'a [Monkey],
+    relief: Relief,
+    modulus: Option<u64>,
+    troop: Troop,
+    overflowed: bool,
+}
+
+impl KeepAway<'_> {
+    /// Whether some worry level got too big for a `u64`.  That stops the game partway through a
+    /// round, with the item that was being thrown gone missing.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    fn monkey_business(&self) -> Result<u64, String> {
+        if self.overflowed {
+            return Err(format!(
+                "a worry level got too big to keep track of in round {}",
+                self.troop.rounds + 1
+            ));
+        }
+        Ok(self.troop.monkey_business())
+    }
+}
+
+impl Simulation for KeepAway<'_> {
+    type State = Troop;
+
+    fn step(&mut self) {
+        if self.overflowed {
+            return;
+        }
+
+        let Troop {
+            items, inspected, ..
+        } = &mut self.troop;
+
+        for (i, monkey) in self.monkeys.iter().enumerate() {
+            for item in std::mem::take(&mut items[i]) {
+                inspected[i] += 1;
+
+                // there's room in a u128 for any u64 squared, so it's only once the relief (if
+                // any) is done that it has to fit back in a u64
+                let item = item as u128;
+                let item = match monkey.operation {
+                    Square => item * item,
+                    Multiply(other) => item * other as u128,
+                    Add(other) => item + other as u128,
+                };
+                let item = match self.relief {
+                    Relief::DivideBy(divisor) => u64::try_from(item / divisor.get() as u128).ok(),
+                    Relief::None => self.modulus.map(|modulus| (item % modulus as u128) as u64),
+                };
+                let Some(item) = item else {
+                    self.overflowed = true;
+                    return;
+                };
+
+                let target = if item % monkey.divisor as u64 == 0 {
+                    monkey.true_target
+                } else {
+                    monkey.false_target
+                };
+
+                items[target].push(item);
             }
         }
 
-        activity.sort_by_key(|&i| std::cmp::Reverse(i));
-        activity.iter().take(2).product()
+        self.troop.rounds += 1;
     }
 
-    pub fn part2(&self) -> u64 {
-        let mut items = self
-            .monkeys
-            .iter()
-            .map(|monkey| monkey.items.iter().map(|&item| item as u64).collect_vec())
-            .collect_vec();
-        let mut activity = vec![0; self.monkeys.len()];
+    fn state(&self) -> &Troop {
+        &self.troop
+    }
 
-        // we'll do all our math modulo the product of all the divisors, since they seem to be small
-        // primes.
-        //
-        // TODO: find the theorem that says we can do math modulo p1*p2*p3*...
-        let modulus: u64 = self.monkeys.iter().map(|i| i.divisor as u64).product();
+    fn is_done(&self) -> bool {
+        self.overflowed
+    }
+}
 
-        for round in 0..10_000 {
-            debug!("round {}", round);
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 8;
 
-            for (i, monkey) in self.monkeys.iter().enumerate() {
-                for item in std::mem::take(&mut items[i]) {
-                    activity[i] += 1;
+    /// `size` monkeys, up to 9 of them, each one's divisor a different one of the first nine
+    /// primes.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let count = size.clamp(2, 9);
 
-                    let item = match monkey.operation {
-                        Square => item.pow(2),
-                        Multiply(other) => item * other as u64,
-                        Add(other) => item + other as u64,
-                    };
-                    let item = item % modulus;
+        let mut roll = |only_adding: bool| {
+            let mut divisors = [2, 3, 5, 7, 11, 13, 17, 19, 23];
+            divisors.shuffle(rng);
+            let squarer = rng.gen_range(0..count);
 
-                    let target = if item % monkey.divisor as u64 == 0 {
-                        monkey.true_target
-                    } else {
-                        monkey.false_target
+            let monkeys = (0..count)
+                .map(|i| {
+                    let true_target = (i + rng.gen_range(1..count)) % count;
+                    let false_target = loop {
+                        let target = (i + rng.gen_range(1..count)) % count;
+                        if target != true_target || count == 2 {
+                            break target;
+                        }
                     };
 
-                    items[target].push(item);
+                    Monkey {
+                        items: (0..rng.gen_range(1..=8))
+                            .map(|_| rng.gen_range(50..100))
+                            .collect(),
+                        operation: match i {
+                            _ if only_adding => Add(rng.gen_range(1..9)),
+                            _ if i == squarer => Square,
+                            _ if rng.gen_bool(0.3) => Multiply(rng.gen_range(2..20)),
+                            _ => Add(rng.gen_range(1..9)),
+                        },
+                        divisor: divisors[i],
+                        true_target,
+                        false_target,
+                    }
+                })
+                .collect_vec();
+
+            Solution {
+                monkeys,
+                config: Config::default(),
+            }
+        };
+
+        // reroll until part 1 doesn't overflow: its worry levels can grow without bound, and
+        // squaring them a few times is plenty.  If that keeps happening, give up on multiplying
+        // at all, since adding is never going to get that far.
+        let fits = (0..REROLLS)
+            .map(|_| roll(false))
+            .find(|solution| solution.part1().is_ok());
+        fits.unwrap_or_else(|| roll(true)).to_string()
+    }
+}
+
+// how many times the generator tries for monkeys that multiply, before settling for ones that don't
+const REROLLS: usize = 100;
+
+// the slow way, with nothing but the puzzle's rules: part 1 follows each item through all the
+// rounds on its own, keeping its worry level exactly, and part 2 keeps each item's remainder by
+// every monkey's divisor, which is all any monkey looks at
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u64, String> {
+            let mut inspected = vec![0; self.monkeys.len()];
+
+            for (start, monkey) in self.monkeys.iter().enumerate() {
+                for &item in &monkey.items {
+                    let (mut holder, mut item) = (start, item as u128);
+                    for _ in 0..self.config.part1_rounds {
+                        // it gets thrown on again in the same round, for as long as it lands with
+                        // a monkey whose turn hasn't come yet
+                        let mut next_turn = 0;
+                        while holder >= next_turn {
+                            let monkey = &self.monkeys[holder];
+                            inspected[holder] += 1;
+                            next_turn = holder + 1;
+
+                            item = match monkey.operation {
+                                Square => item.checked_mul(item),
+                                Multiply(other) => item.checked_mul(other as u128),
+                                Add(other) => item.checked_add(other as u128),
+                            }
+                            .ok_or("a worry level got too big")?
+                                / self.config.part1_relief.get() as u128;
+
+                            holder = match item % monkey.divisor as u128 {
+                                0 => monkey.true_target,
+                                _ => monkey.false_target,
+                            };
+                        }
+                    }
+                }
+            }
+
+            Ok(monkey_business(inspected))
+        }
+
+        fn reference_part2(&self) -> Result<u64, String> {
+            let divisors = self
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.divisor as u64)
+                .collect_vec();
+            // for every item, its worry level's remainder by each of the divisors, in order
+            let mut items = self
+                .monkeys
+                .iter()
+                .map(|monkey| {
+                    monkey
+                        .items
+                        .iter()
+                        .map(|&item| divisors.iter().map(|d| item as u64 % d).collect_vec())
+                        .collect_vec()
+                })
+                .collect_vec();
+            let mut inspected = vec![0; self.monkeys.len()];
+
+            for _ in 0..self.config.part2_rounds {
+                for (i, monkey) in self.monkeys.iter().enumerate() {
+                    for remainders in std::mem::take(&mut items[i]) {
+                        inspected[i] += 1;
+
+                        let remainders = remainders
+                            .into_iter()
+                            .zip(&divisors)
+                            .map(|(item, d)| match monkey.operation {
+                                Square => item * item % d,
+                                Multiply(other) => item * other as u64 % d,
+                                Add(other) => (item + other as u64) % d,
+                            })
+                            .collect_vec();
+
+                        let target = match remainders[i] {
+                            0 => monkey.true_target,
+                            _ => monkey.false_target,
+                        };
+                        items[target].push(remainders);
+                    }
                 }
             }
+
+            Ok(monkey_business(inspected))
         }
+    }
+
+    fn monkey_business(mut inspected: Vec<u64>) -> u64 {
+        inspected.sort();
+        inspected.iter().rev().take(2).product()
+    }
+}
 
-        activity.sort_by_key(|&i| std::cmp::Reverse(i));
-        activity.iter().take(2).product()
+prelude::example_tests!();
+prelude::generator_tests!(8);
+prelude::parser_tests!(8);
+prelude::differential_tests!(
+    8,
+    Config {
+        part2_rounds: 1000,
+        ..Config::default()
+    }
+);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn fewer_rounds() {
+        let config = Config {
+            part2_rounds: 20,
+            ..Config::default()
+        };
+        let solution = Solution::with_config(EXAMPLE, config).unwrap();
+        // the puzzle's example says the most active monkeys inspected 103 and 99 items by then
+        assert_eq!(solution.part2(), Ok(103 * 99));
+    }
+
+    #[test]
+    fn no_monkeys() {
+        let error = Solution::new("").err().unwrap();
+        assert_eq!((error.line, error.column), (1, 1));
+    }
+
+    #[test]
+    fn too_worried() {
+        let monkey = |id: usize, divisor: u32| {
+            format!(
+                "Monkey {}:\n\
+                 \x20 Starting items: 99\n\
+                 \x20 Operation: new = old * old\n\
+                 \x20 Test: divisible by {}\n\
+                 \x20   If true: throw to monkey 0\n\
+                 \x20   If false: throw to monkey 0\n",
+                id, divisor
+            )
+        };
+
+        // squaring every round gets too big for part 1, but part 2's modulus is only 2, and the one
+        // monkey just inspects its one item every round
+        let solution = Solution::new(&monkey(0, 2)).unwrap();
+        assert!(solution.part1().is_err());
+        assert_eq!(solution.part2(), Ok(10_000));
+
+        // and three divisors that big multiply out to too big a modulus for part 2
+        let input = (0..3).map(|id| monkey(id, u32::MAX)).join("\n");
+        let solution = Solution::new(&input).unwrap();
+        assert!(solution.part2().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(seed: u64) {
+            let input = Solution::generate_seeded(seed, 8);
+            let solution = Solution::new(&input).unwrap();
+            prop_assert_eq!(solution.to_string(), input);
+
+            let reparsed = Solution::new(&solution.to_string()).unwrap();
+            prop_assert_eq!(reparsed.monkeys, solution.monkeys);
+        }
     }
 }
@@ -1,95 +1,178 @@
-use petgraph::prelude::*;
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u32 = 31;
+pub const EXAMPLE_PART2: u32 = 29;
+
+#[derive(Serialize)]
 pub struct Solution {
-    lines: Vec<String>,
+    heights: Grid<u8>,
+    start: NodeName,
+    end: NodeName,
 }
 
 type NodeName = (usize, usize);
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = u32;
+    type Part2 = u32;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
-        Solution {
-            lines: input.lines().map(str::to_owned).collect(),
-        }
+        let map = Grid::parse(input, "a height from a to z, S, or E", |c| {
+            matches!(c, 'a'..='z' | 'S' | 'E').then_some(c)
+        })?;
+        let [start, end] = ['S', 'E'].map(|marker| {
+            map.position(|&c| c == marker).ok_or_else(|| {
+                ParseError::end_of_input(
+                    map.height() + 1,
+                    format!("an {} somewhere in the map", marker),
+                )
+            })
+        });
+
+        Ok(Solution {
+            heights: map.map(|&c| match c {
+                'S' => b'a',
+                'E' => b'z',
+                x => x as u8,
+            }),
+            start: start?,
+            end: end?,
+        })
+    }
+
+    fn part1(&self) -> Result<u32, String> {
+        let search = search::bfs_until(
+            self.end,
+            |&position| self.steps_down(position),
+            |&position| position == self.start,
+        );
+        search
+            .distance(&self.start)
+            .map(|steps| steps as u32)
+            .ok_or_else(no_path)
     }
 
-    pub fn part1(&self) -> u32 {
-        let (graph, start, end) = self.to_graph();
+    fn part2(&self) -> Result<u32, String> {
+        // searching outward from the end, the first square at the bottom is the closest one
+        let search = search::bfs_until(
+            self.end,
+            |&position| self.steps_down(position),
+            |&position| self.heights[position] == b'a',
+        );
+
+        debug!("costs: {:#?}", search.distances());
 
-        let costs = petgraph::algo::dijkstra(&graph, end, Some(start), |_| 1);
-        *costs.get(&start).unwrap()
+        // S is at the bottom too, so if there's no way from any of them, there's none from S
+        let goal = search.goal().ok_or_else(no_path)?;
+        Ok(search.distance(goal).unwrap() as u32)
     }
+}
 
-    pub fn part2(&self) -> u32 {
-        let (graph, _, end) = self.to_graph();
-        let costs = petgraph::algo::dijkstra(&graph, end, None, |_| 1);
+fn no_path() -> String {
+    "no path from S to E".to_owned()
+}
 
-        debug!("costs: {:#?}", costs);
+impl Solution {
+    // the squares that we could have climbed to `position` from, so we can search backward from the
+    // end
+    fn steps_down(&self, position: NodeName) -> impl Iterator<Item = NodeName> + '_ {
+        let height = self.heights[position];
+        self.heights
+            .neighbors4(position)
+            .filter(move |&other| height <= self.heights[other] + 1)
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 160;
 
-        let mut min_cost = u32::MAX;
+    /// A map `size` squares wide (and at least 26, to have room to climb all the way to z) and a
+    /// quarter as tall.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let width = size.max(26);
+        let height = width / 4;
+        let mut map = vec![vec![b'a'; width]; height];
+        for square in map.iter_mut().flatten() {
+            *square = rng.gen_range(b'a'..=b'z');
+        }
 
-        for (i, row) in self.lines.iter().enumerate() {
-            for (j, cell) in row.bytes().enumerate() {
-                if cell == b'S' || cell == b'a' {
-                    debug!("looking at {:?}", (i, j));
-                    if let Some(&cost) = costs.get(&(i, j)) {
-                        min_cost = std::cmp::min(min_cost, cost)
-                    }
-                }
+        // make sure there's a way up: a staircase from the top-left corner to the bottom-right,
+        // never climbing more than one letter at a time
+        let length = width + height - 2;
+        let (mut row, mut column) = (0, 0);
+        for step in 0..=length {
+            map[row][column] = b'a' + (step * 25 / length) as u8;
+            if column == width - 1 || (row < height - 1 && rng.gen_bool(0.5)) {
+                row += 1;
+            } else {
+                column += 1;
             }
         }
+        map[0][0] = b'S';
+        map[height - 1][width - 1] = b'E';
 
-        min_cost
+        map.into_iter()
+            .map(|row| String::from_utf8(row).unwrap() + "\n")
+            .collect()
     }
+}
 
-    fn to_graph(&self) -> (DiGraphMap<NodeName, ()>, NodeName, NodeName) {
-        let mut graph = DiGraphMap::new();
-        let mut start = None;
-        let mut end = None;
-
-        for (i, row) in self.lines.iter().enumerate() {
-            for (j, cell) in row.bytes().enumerate() {
-                let height = match cell {
-                    b'S' => {
-                        start = Some((i, j));
-                        b'a'
-                    }
-                    b'E' => {
-                        end = Some((i, j));
-                        b'z'
-                    }
-                    x => x,
-                };
-
-                for (other_i, other_j) in [
-                    (i.wrapping_sub(1), j),
-                    (i + 1, j),
-                    (i, j.wrapping_sub(1)),
-                    (i, j + 1),
-                ] {
-                    let Some(&other_cell) = self.lines.get(other_i).and_then(|line| line.as_bytes().get(other_j)) else {
-                        continue;
-                    };
-                    let other_height = match other_cell {
-                        b'S' => b'a',
-                        b'E' => b'z',
-                        x => x,
-                    };
-                    if other_height <= height + 1 {
-                        graph.add_edge((other_i, other_j), (i, j), ());
-                    }
-                }
-            }
+// the slow way: climbs forward from every square it could start on, searching the whole map each
+// time, instead of searching backward from the end once
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        fn steps_up(&self, position: NodeName) -> impl Iterator<Item = NodeName> + '_ {
+            let height = self.heights[position];
+            self.heights
+                .neighbors4(position)
+                .filter(move |&other| self.heights[other] <= height + 1)
+        }
+
+        fn climb(&self, start: NodeName) -> Option<u32> {
+            let search = search::bfs(start, |&position| self.steps_up(position));
+            search.distance(&self.end).map(|steps| steps as u32)
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u32, String> {
+            self.climb(self.start).ok_or_else(no_path)
+        }
+
+        fn reference_part2(&self) -> Result<u32, String> {
+            self.heights
+                .iter()
+                .filter(|&(_, &height)| height == b'a')
+                .filter_map(|(position, _)| self.climb(position))
+                .min()
+                .ok_or_else(no_path)
         }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(30);
+prelude::parser_tests!(30);
+prelude::differential_tests!(30);
+
+#[cfg(test)]
+mod test {
+    use super::*;
 
-        (
-            graph,
-            start.expect("didn't find S"),
-            end.expect("didn't find E"),
-        )
+    #[test]
+    fn no_way_up() {
+        let solution = Solution::new("SyE\n").unwrap();
+        assert_eq!(solution.part1(), Err(no_path()));
+        assert_eq!(solution.part2(), Err(no_path()));
     }
 }
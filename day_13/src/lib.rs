@@ -1,20 +1,39 @@
 use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt::{self, Display};
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::multi::separated_list0;
 use nom::IResult;
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 
 use Packet::*;
 
-#[derive(Debug, PartialEq, Eq)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 13;
+pub const EXAMPLE_PART2: usize = 140;
+
+// untagged, so it comes out looking just like it went in
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
 enum Packet {
     Integer(u32),
     List(Vec<Packet>),
 }
 
+impl Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Integer(i) => write!(f, "{}", i),
+            List(packets) => write!(f, "[{}]", packets.iter().join(",")),
+        }
+    }
+}
+
 fn parse_packet(input: &str) -> IResult<&str, Packet> {
     alt((
         |input| -> IResult<_, _> {
@@ -30,6 +49,15 @@ fn parse_packet(input: &str) -> IResult<&str, Packet> {
     ))(input)
 }
 
+// equal in the puzzle's sense, so that 2 and [[2]] are the same packet as far as sorting goes
+impl PartialEq for Packet {
+    fn eq(&self, other: &Packet) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet {}
+
 impl PartialOrd<Packet> for Packet {
     fn partial_cmp(&self, other: &Packet) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -71,44 +99,66 @@ impl Ord for Packet {
     }
 }
 
+#[derive(Serialize)]
 pub struct Solution {
     packets: Vec<(Packet, Packet)>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+/// Writes the packets back out the way the input had them.
+impl Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (left, right)) in self.packets.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}\n{}", left, right)?;
+        }
+        Ok(())
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = usize;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
         let mut packets = vec![];
 
-        let mut lines = input.lines();
-        while let Some(line) = lines.next() {
-            let (remaining, packet) = parse_packet(line).expect("couldn't parse");
-            assert!(remaining.is_empty());
-            let line = lines.next().expect("need two packets");
-            let (remaining, second_packet) = parse_packet(line).expect("couldn't parse");
-            assert!(remaining.is_empty());
+        let mut lines = input.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let packet = parse_line(i + 1, line, "a packet", parse_packet)?;
+            let Some((i, line)) = lines.next() else {
+                return Err(ParseError::end_of_input(i + 2, "a second packet"));
+            };
+            let second_packet = parse_line(i + 1, line, "a packet", parse_packet)?;
 
             packets.push((packet, second_packet));
 
             // eat the intervening newline
-            let _ = lines.next();
+            match lines.next() {
+                None | Some((_, "")) => (),
+                Some((i, line)) => return Err(ParseError::at(i + 1, line, line, "an empty line")),
+            }
         }
 
         debug!("parsed: {:#?}", packets);
 
-        Solution { packets }
+        Ok(Solution { packets })
     }
 
-    pub fn part1(&self) -> usize {
-        self.packets
+    fn part1(&self) -> Result<usize, Infallible> {
+        Ok(self
+            .packets
             .iter()
             .enumerate()
             .filter_map(|(index, (left, right))| if left < right { Some(index + 1) } else { None })
-            .sum()
+            .sum())
     }
 
-    pub fn part2(&self) -> usize {
+    fn part2(&self) -> Result<usize, Infallible> {
         let divider1 = List(vec![List(vec![Integer(2)])]);
         let divider2 = List(vec![List(vec![Integer(6)])]);
         let mut all_packets = self
@@ -129,6 +179,186 @@ impl Solution {
             .position(|&i| std::ptr::eq(i, &divider2))
             .unwrap();
 
-        (index_1 + 1) * (index_2 + 1)
+        Ok((index_1 + 1) * (index_2 + 1))
+    }
+}
+
+// a list nested at most `depth` deep, written the way the input has it
+fn generate_packet(rng: &mut impl Rng, depth: u32) -> String {
+    let items = (0..rng.gen_range(0..=5))
+        .map(|_| {
+            if depth > 0 && rng.gen_bool(0.3) {
+                generate_packet(rng, depth - 1)
+            } else {
+                rng.gen_range(0..=10).to_string()
+            }
+        })
+        .join(",");
+    format!("[{}]", items)
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 150;
+
+    /// `size` pairs of packets.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| format!("{}\n{}\n", generate_packet(rng, 4), generate_packet(rng, 4)))
+            .join("\n")
+    }
+}
+
+// the slow way: compares the packets a token at a time, the way they're written, wrapping integers
+// in brackets right there in the text when they're up against a list; and finds the dividers by
+// counting what comes before them instead of sorting
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Token {
+        Open,
+        Close,
+        Integer(u32),
+    }
+
+    fn tokens(packet: &Packet) -> Vec<Token> {
+        let text = packet.to_string();
+        let mut tokens = vec![];
+        let mut rest = text.as_str();
+        while let Some(c) = rest.chars().next() {
+            match c {
+                '[' => tokens.push(Token::Open),
+                ']' => tokens.push(Token::Close),
+                ',' => (),
+                _ => {
+                    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap();
+                    tokens.push(Token::Integer(rest[..digits].parse().unwrap()));
+                    rest = &rest[digits..];
+                    continue;
+                }
+            }
+            rest = &rest[1..];
+        }
+        tokens
+    }
+
+    fn compare(left: &Packet, right: &Packet) -> Ordering {
+        let (mut left, mut right) = (tokens(left), tokens(right));
+        let mut i = 0;
+        loop {
+            match (left.get(i).copied(), right.get(i).copied()) {
+                (None, None) => return Ordering::Equal,
+                (Some(l), Some(r)) if l == r => (),
+                (Some(Token::Integer(l)), Some(Token::Integer(r))) => return l.cmp(&r),
+                // one side ran out of things in the list first
+                (Some(Token::Close), _) => return Ordering::Less,
+                (_, Some(Token::Close)) => return Ordering::Greater,
+                (Some(Token::Integer(l)), Some(Token::Open)) => {
+                    left.splice(i..=i, [Token::Open, Token::Integer(l), Token::Close]);
+                }
+                (Some(Token::Open), Some(Token::Integer(r))) => {
+                    right.splice(i..=i, [Token::Open, Token::Integer(r), Token::Close]);
+                }
+                _ => unreachable!("both packets end with the same ], so neither runs out first"),
+            }
+            i += 1;
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<usize, Infallible> {
+            Ok((1..=self.packets.len())
+                .filter(|&index| {
+                    let (left, right) = &self.packets[index - 1];
+                    compare(left, right) == Ordering::Less
+                })
+                .sum())
+        }
+
+        fn reference_part2(&self) -> Result<usize, Infallible> {
+            let dividers = [2, 6].map(|i| List(vec![List(vec![Integer(i)])]));
+            // anything equal to a divider goes ahead of it, the way a stable sort leaves it, and
+            // the first divider goes ahead of the second
+            let position = |divider: &Packet| {
+                1 + self
+                    .packets
+                    .iter()
+                    .flat_map(|(left, right)| [left, right])
+                    .filter(|packet| compare(packet, divider) != Ordering::Greater)
+                    .count()
+            };
+
+            Ok(position(&dividers[0]) * (position(&dividers[1]) + 1))
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(30);
+prelude::parser_tests!(30);
+prelude::differential_tests!(30);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn export_looks_like_input() {
+        let solution = Solution::new("[1,[2,[]]]\n[[4]]\n").unwrap();
+        let exported: serde_json::Value = serde_json::from_str(&solution.export()).unwrap();
+        assert_eq!(
+            exported,
+            serde_json::json!({"packets": [[[1, [2, []]], [[4]]]]})
+        );
+    }
+
+    fn packet() -> impl Strategy<Value = Packet> {
+        (0..=10u32)
+            .prop_map(Integer)
+            .prop_recursive(4, 32, 5, |inner| {
+                prop::collection::vec(inner, 0..5).prop_map(List)
+            })
+    }
+
+    // the same packet as far as the puzzle's concerned, with every integer put in a list by itself
+    fn wrap_integers(packet: &Packet) -> Packet {
+        match packet {
+            Integer(i) => List(vec![Integer(*i)]),
+            List(packets) => List(packets.iter().map(wrap_integers).collect()),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(seed: u64) {
+            let input = Solution::generate_seeded(seed, 20);
+            let solution = Solution::new(&input).unwrap();
+            prop_assert_eq!(solution.to_string(), input);
+        }
+
+        #[test]
+        fn packet_round_trip(packet in packet()) {
+            let printed = packet.to_string();
+            let (rest, parsed) = parse_packet(&printed).unwrap();
+            prop_assert_eq!(rest, "");
+            prop_assert_eq!(parsed.to_string(), printed);
+        }
+
+        #[test]
+        fn total_order(a in packet(), b in packet(), c in packet()) {
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a.cmp(&b) == Ordering::Equal, a == b);
+            prop_assert_eq!(a.cmp(&a), Ordering::Equal);
+            prop_assert_eq!(a.cmp(&wrap_integers(&a)), Ordering::Equal);
+            prop_assert_eq!(&a, &wrap_integers(&a));
+
+            let mut sorted = [&a, &b, &c];
+            sorted.sort();
+            prop_assert!(sorted[0] <= sorted[1] && sorted[1] <= sorted[2]);
+            prop_assert!(sorted[0] <= sorted[2]);
+        }
     }
 }
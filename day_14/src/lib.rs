@@ -0,0 +1,482 @@
+use std::convert::Infallible;
+
+use js_sys::Function;
+use prelude::log::debug;
+use prelude::rand::Rng;
+use prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Document, HtmlDivElement, HtmlElement};
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: usize = 24;
+pub const EXAMPLE_PART2: usize = 93;
+
+// far bigger than any cave the puzzle has, and far enough from the ends of an i32 that the pile on
+// the floor can spread out as wide as it's deep without overflowing
+const COORDINATE_LIMIT: i32 = 1_000_000;
+
+/// Where the sand comes from, and how far below the lowest rock the floor is in part 2.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub source: Point2,
+    pub floor_distance: i32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            source: Point2::new(500, 0),
+            floor_distance: 2,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Solution {
+    paths: Vec<Vec<Point2>>,
+    #[serde(skip)]
+    rock: SparseGrid<Tile>,
+    // the row of the floor in part 2
+    #[serde(skip)]
+    floor: i32,
+    #[serde(skip)]
+    config: Config,
+}
+
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
+        init();
+
+        let paths = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| parse_path(i + 1, line))
+            .collect::<Result<Vec<_>, _>>()?;
+        if paths.is_empty() {
+            return Err(ParseError::end_of_input(1, "a path of rock"));
+        }
+
+        debug!("parsed: {:?}", paths);
+
+        let in_range = |coordinate: i32| coordinate.abs() <= COORDINATE_LIMIT;
+        if !in_range(config.source.x) || !in_range(config.source.y) {
+            return Err(ParseError::config(
+                format!(
+                    "a source from -{} to {} each way",
+                    COORDINATE_LIMIT, COORDINATE_LIMIT
+                ),
+                format!("a source at {},{}", config.source.x, config.source.y),
+            ));
+        }
+
+        let mut rock = SparseGrid::new();
+        for path in &paths {
+            rock.insert(path[0], Tile::Rock);
+            for (&from, &to) in path.iter().zip(&path[1..]) {
+                let step = (to - from).signum();
+                let mut point = from;
+                while point != to {
+                    point += step;
+                    rock.insert(point, Tile::Rock);
+                }
+            }
+        }
+
+        let lowest_rock = paths.iter().flatten().map(|point| point.y).max().unwrap();
+        let Some(floor) = lowest_rock
+            .checked_add(config.floor_distance)
+            .filter(|&floor| in_range(floor))
+        else {
+            return Err(ParseError::config(
+                format!(
+                    "a floor_distance that puts the floor from -{} to {}",
+                    COORDINATE_LIMIT, COORDINATE_LIMIT
+                ),
+                format!("a floor_distance of {}", config.floor_distance),
+            ));
+        };
+        // a floor above the source would let the sand fall forever, so it's never higher than right
+        // under it
+        let floor = floor.max(config.source.y + 1);
+
+        Ok(Solution {
+            paths,
+            rock,
+            floor,
+            config,
+        })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = usize;
+    type Part2 = usize;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
+
+    fn part1(&self) -> Result<usize, Infallible> {
+        let mut sand = self.sand(false);
+        sand.run();
+        Ok(sand.grains())
+    }
+
+    fn part2(&self) -> Result<usize, Infallible> {
+        let mut sand = self.sand(true);
+        sand.run();
+        Ok(sand.grains())
+    }
+}
+
+// "x,y -> x,y -> ...", where each point is straight across or straight down from the one before
+fn parse_path(line_number: usize, line: &str) -> Result<Vec<Point2>, ParseError> {
+    let mut path: Vec<Point2> = vec![];
+    for word in line.split(" -> ") {
+        let Some((x, y)) = word.split_once(',') else {
+            return Err(ParseError::at(
+                line_number,
+                line,
+                word,
+                "a point like 498,4",
+            ));
+        };
+        let coordinate = |word: &str, expected: &str| {
+            let coordinate: i32 = parse_word(line_number, line, word, expected)?;
+            if coordinate.abs() > COORDINATE_LIMIT {
+                return Err(ParseError::at(
+                    line_number,
+                    line,
+                    word,
+                    format!(
+                        "{} from -{} to {}",
+                        expected, COORDINATE_LIMIT, COORDINATE_LIMIT
+                    ),
+                ));
+            }
+            Ok(coordinate)
+        };
+        let point = Point2::new(
+            coordinate(x, "an x coordinate")?,
+            coordinate(y, "a y coordinate")?,
+        );
+
+        if let Some(&previous) = path.last() {
+            if previous.x != point.x && previous.y != point.y {
+                return Err(ParseError::at(
+                    line_number,
+                    line,
+                    word,
+                    "a point in a straight line from the one before it",
+                ));
+            }
+        }
+        path.push(point);
+    }
+
+    Ok(path)
+}
+
+impl Solution {
+    /// Sand falling into the cave, with a floor under it or without.
+    pub fn sand(&self, floor: bool) -> Sand {
+        Sand::new(
+            self.rock.clone(),
+            self.config.source,
+            floor.then_some(self.floor),
+        )
+    }
+
+    pub fn render(&self, document: Document, target: &HtmlDivElement) -> Result<Renderer, JsValue> {
+        while let Some(child) = target.first_child() {
+            let _ = target.remove_child(&child); // if somehow the child already got removed, not my problem!
+        }
+
+        // big enough for the pile that builds up on the floor, which is wider than anything part 1
+        // can make
+        let floor = self.floor;
+        let spread = floor - self.config.source.y;
+        // there's always at least one path of rock
+        let (top_left, bottom_right) = self.rock.bounds().unwrap();
+        let left = top_left.x.min(self.config.source.x - spread);
+        let right = bottom_right.x.max(self.config.source.x + spread);
+        let top = top_left.y.min(self.config.source.y);
+
+        let style = target.style();
+        style.set_property("--left", &left.to_string())?;
+        style.set_property("--top", &top.to_string())?;
+        style.set_property("--width", &(right - left + 1).to_string())?;
+        style.set_property("--height", &(floor - top + 1).to_string())?;
+
+        let mut renderer = Renderer {
+            document,
+            target: target.clone(),
+            rock: self.rock.clone(),
+            source: self.config.source,
+            floor: self.floor,
+            sand: None,
+        };
+        for (point, _) in self.rock.iter() {
+            renderer.draw(point, "rock")?;
+        }
+
+        Ok(renderer)
+    }
+}
+
+/// What's in a spot in the cave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Rock,
+    Sand,
+}
+
+/// Sand falling from the source one grain per step, until one falls past all the rock into the
+/// abyss below, or the pile reaches all the way up to the source.
+#[derive(Debug, Clone)]
+pub struct Sand {
+    // the rock, and every grain that's come to rest on it
+    cave: SparseGrid<Tile>,
+    grains: usize,
+    source: Point2,
+    // the row that nothing can fall into, if there's a floor
+    floor: Option<i32>,
+    lowest_rock: i32,
+    last_grain: Option<Point2>,
+    done: bool,
+}
+
+impl Sand {
+    fn new(rock: SparseGrid<Tile>, source: Point2, floor: Option<i32>) -> Self {
+        let lowest_rock = rock.bounds().map_or(0, |(_, bottom_right)| bottom_right.y);
+        Sand {
+            cave: rock,
+            grains: 0,
+            source,
+            floor,
+            lowest_rock,
+            last_grain: None,
+            done: false,
+        }
+    }
+
+    /// How many grains have come to rest.
+    pub fn grains(&self) -> usize {
+        self.grains
+    }
+
+    /// Where the grain from the last step came to rest, unless it fell into the abyss.
+    pub fn last_grain(&self) -> Option<Point2> {
+        self.last_grain
+    }
+
+    fn is_open(&self, point: Point2) -> bool {
+        self.floor != Some(point.y) && !self.cave.contains(point)
+    }
+}
+
+impl Simulation for Sand {
+    /// The rock, and every grain that has come to rest.
+    type State = SparseGrid<Tile>;
+
+    fn step(&mut self) {
+        if self.done {
+            return;
+        }
+
+        let mut grain = self.source;
+        loop {
+            if self.floor.is_none() && grain.y > self.lowest_rock {
+                debug!("fell into the abyss after {} grains", self.grains);
+                self.last_grain = None;
+                self.done = true;
+                return;
+            }
+
+            let next = [0, -1, 1]
+                .into_iter()
+                .map(|dx| grain + Point2::new(dx, 1))
+                .find(|&next| self.is_open(next));
+            match next {
+                Some(next) => grain = next,
+                None => break,
+            }
+        }
+
+        self.cave.insert(grain, Tile::Sand);
+        self.grains += 1;
+        self.last_grain = Some(grain);
+        if grain == self.source {
+            debug!("blocked the source after {} grains", self.grains);
+            self.done = true;
+        }
+    }
+
+    fn state(&self) -> &SparseGrid<Tile> {
+        &self.cave
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// Draws the sand piling up, some number of grains per animation frame.  Only one of the parts
+/// can be ticked: whichever one goes first decides whether there's a floor.
+pub struct Renderer {
+    document: Document,
+    target: HtmlDivElement,
+    rock: SparseGrid<Tile>,
+    source: Point2,
+    floor: i32,
+    sand: Option<Sand>,
+}
+
+impl Renderer {
+    fn draw(&mut self, point: Point2, class: &str) -> Result<HtmlElement, JsValue> {
+        let div = self
+            .document
+            .create_element("div")?
+            .dyn_into::<HtmlElement>()?;
+        div.set_class_name(class);
+        let style = div.style();
+        style.set_property("--x", &point.x.to_string())?;
+        style.set_property("--y", &point.y.to_string())?;
+        self.target.append_child(&div)?;
+        Ok(div)
+    }
+
+    fn tick(&mut self, floor: bool, grains: u32, callback: &Function) -> Result<(), JsValue> {
+        if self.sand.is_none() {
+            let sand = Sand::new(self.rock.clone(), self.source, floor.then_some(self.floor));
+            if let Some(floor) = sand.floor {
+                self.draw(Point2::new(0, floor), "floor")?;
+            }
+            self.sand = Some(sand);
+        }
+
+        for _ in 0..grains {
+            let sand = self.sand.as_mut().unwrap();
+            if sand.is_done() {
+                break;
+            }
+            sand.step();
+            if let Some(grain) = sand.last_grain() {
+                self.draw(grain, "sand")?;
+            }
+        }
+
+        if self.sand.as_ref().unwrap().is_done() {
+            debug!("done!");
+            return Ok(());
+        }
+
+        web_sys::window()
+            .ok_or("there's no window to animate in")?
+            .request_animation_frame(callback)?;
+        Ok(())
+    }
+
+    /// Drops `grains` more grains with no floor, then calls `callback` on the next animation frame
+    /// if there's more to drop.
+    pub fn tick_part1(&mut self, grains: u32, callback: &Function) -> Result<(), JsValue> {
+        self.tick(false, grains, callback)
+    }
+
+    /// Drops `grains` more grains onto the floor, then calls `callback` on the next animation
+    /// frame if there's more to drop.
+    pub fn tick_part2(&mut self, grains: u32, callback: &Function) -> Result<(), JsValue> {
+        self.tick(true, grains, callback)
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 150;
+
+    /// `size` paths of rock (and at least one), each with a few straight segments, scattered
+    /// below the source, and deeper the more of them there are.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                let depth = rng.gen_range(10..=10 + size as i32);
+                let mut point = Point2::new(rng.gen_range(470..=530), depth);
+                let mut path = vec![point];
+                for _ in 0..rng.gen_range(1..=5) {
+                    let length = rng.gen_range(1..=8) * if rng.gen() { 1 } else { -1 };
+                    if rng.gen() {
+                        point.x += length;
+                    } else {
+                        point.y = (point.y + length).max(1);
+                    }
+                    path.push(point);
+                }
+                path.into_iter()
+                    .map(|point| format!("{},{}", point.x, point.y))
+                    .join(" -> ")
+                    + "\n"
+            })
+            .collect()
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn diagonal_path() {
+        let error = Solution::new("498,4 -> 498,6\n503,4 -> 502,5\n")
+            .err()
+            .unwrap();
+        assert_eq!((error.line, error.column), (2, 10));
+    }
+
+    #[test]
+    fn example_piles() {
+        let solution = Solution::new(EXAMPLE).unwrap();
+
+        // the first grain lands on the lower path, right under the source
+        let mut sand = solution.sand(false);
+        sand.step();
+        assert_eq!(sand.last_grain(), Some(Point2::new(500, 8)));
+
+        sand.run();
+        assert_eq!(sand.last_grain(), None);
+
+        let mut sand = solution.sand(true);
+        sand.run();
+        assert_eq!(sand.last_grain(), Some(Point2::new(500, 0)));
+        // the floor is two below the lowest rock, so nothing rests lower than the row above it
+        assert!(sand.state().iter().all(|(point, _)| point.y <= 10));
+        assert_eq!(sand.state().get(Point2::new(490, 10)), Some(&Tile::Sand));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let error = Solution::new("498,4 -> 498,6\n500,2147483647 -> 500,2147483647\n")
+            .err()
+            .unwrap();
+        assert_eq!((error.line, error.column), (2, 5));
+
+        let config = Config {
+            floor_distance: i32::MAX,
+            ..Config::default()
+        };
+        assert!(Solution::with_config(EXAMPLE, config).is_err());
+
+        let config = Config {
+            source: Point2::new(500, i32::MAX),
+            ..Config::default()
+        };
+        assert!(Solution::with_config(EXAMPLE, config).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use std::ops::RangeInclusive;
+
+use nom::bytes::complete::tag;
+use nom::IResult;
+use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
+use prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 26;
+pub const EXAMPLE_PART2: i64 = 56000011;
+
+/// A sensor, and the closest beacon to it.  There's nothing any closer that it would have missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Sensor {
+    pub position: Point2,
+    pub beacon: Point2,
+}
+
+impl Sensor {
+    /// How far away the sensor can rule out any other beacons.  That can be further than an `i32`
+    /// goes, when the sensor and its beacon are at opposite ends of one.
+    pub fn radius(&self) -> i64 {
+        let (dx, dy) = (
+            self.position.x as i64 - self.beacon.x as i64,
+            self.position.y as i64 - self.beacon.y as i64,
+        );
+        dx.abs() + dy.abs()
+    }
+
+    /// The positions on row `y` that are no farther from this sensor than its beacon, if any are.
+    pub fn row_coverage(&self, y: i32) -> Option<RangeInclusive<i64>> {
+        let x = self.position.x as i64;
+        let reach = self.radius() - (y as i64 - self.position.y as i64).abs();
+        (reach >= 0).then(|| x - reach..=x + reach)
+    }
+}
+
+fn parse_point(input: &str) -> IResult<&str, Point2> {
+    let (input, _) = tag("x=")(input)?;
+    let (input, x) = nom::character::complete::i32(input)?;
+    let (input, _) = tag(", y=")(input)?;
+    let (input, y) = nom::character::complete::i32(input)?;
+    Ok((input, Point2::new(x, y)))
+}
+
+fn parse_sensor(input: &str) -> IResult<&str, Sensor> {
+    let (input, _) = tag("Sensor at ")(input)?;
+    let (input, position) = parse_point(input)?;
+    let (input, _) = tag(": closest beacon is at ")(input)?;
+    let (input, beacon) = parse_point(input)?;
+    Ok((input, Sensor { position, beacon }))
+}
+
+/// Which row to count the covered positions of in part 1, and how far the search for the distress
+/// beacon goes in part 2: from 0 through `search_bound` in both directions.  The example is a
+/// smaller version of the puzzle, with row 10 and a bound of 20.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub row: i32,
+    pub search_bound: i32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            row: 2_000_000,
+            search_bound: 4_000_000,
+        }
+    }
+}
+
+impl Config {
+    pub fn example() -> Self {
+        Config {
+            row: 10,
+            search_bound: 20,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Solution {
+    sensors: Vec<Sensor>,
+    #[serde(skip)]
+    config: Config,
+}
+
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
+        init();
+
+        let sensors = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                parse_line(i + 1, line, "a sensor and its closest beacon", parse_sensor)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        debug!("parsed: {:?}", sensors);
+
+        Ok(Solution { sensors, config })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = i64;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
+
+    fn part1(&self) -> Result<u64, String> {
+        let row = self.config.row;
+        let covered = self
+            .row_coverage(row)
+            .into_iter()
+            .map(|range| (range.end() - range.start()) as u64 + 1)
+            .sum::<u64>();
+
+        // every beacon is covered by its own sensor, but it's certainly not somewhere a beacon
+        // can't be
+        let beacons = self
+            .sensors
+            .iter()
+            .map(|sensor| sensor.beacon)
+            .filter(|beacon| beacon.y == row)
+            .collect::<HashSet<_>>();
+
+        Ok(covered - beacons.len() as u64)
+    }
+
+    fn part2(&self) -> Result<i64, String> {
+        let beacon = first_gap(&self.sensors, self.config.search_bound).ok_or_else(|| {
+            format!(
+                "every position from 0 through {} is covered",
+                self.config.search_bound
+            )
+        })?;
+        debug!("the distress beacon is at {:?}", beacon);
+
+        Ok(beacon.x as i64 * 4_000_000 + beacon.y as i64)
+    }
+}
+
+impl Solution {
+    /// The positions on row `y` that some sensor covers, as sorted ranges that neither overlap nor
+    /// touch each other.
+    pub fn row_coverage(&self, y: i32) -> Vec<RangeInclusive<i64>> {
+        let mut ranges = self
+            .sensors
+            .iter()
+            .filter_map(|sensor| sensor.row_coverage(y))
+            .collect_vec();
+        ranges.sort_by_key(|range| *range.start());
+
+        let mut merged: Vec<RangeInclusive<i64>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end() + 1 => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+}
+
+// what looking along one row of the search area found
+enum RowSearch {
+    Gap(i32),
+    // how many more rows after this one are sure to be covered, too
+    CoveredFor(i64),
+}
+
+// looks for the first position on row `y`, from 0 through `bound`, that none of the `sensors`
+// covers.  Every sensor's range on a row can only move each of its ends by one from row to row, so
+// when the row is covered, how much the ranges that cover it overlap says how many rows can be
+// skipped.
+fn search_row(sensors: &[Sensor], y: i32, bound: i32) -> RowSearch {
+    let mut ranges = sensors
+        .iter()
+        .filter_map(|sensor| sensor.row_coverage(y))
+        .collect_vec();
+    ranges.sort_by_key(|range| *range.start());
+
+    // everything from 0 through here is covered
+    let mut covered_to: i64 = -1;
+    let mut skip = i64::MAX;
+    for range in ranges {
+        if *range.start() > covered_to + 1 {
+            break;
+        }
+        if *range.end() <= covered_to {
+            continue;
+        }
+
+        skip = skip.min(if covered_to < 0 {
+            // only this range's start moves toward the left edge
+            -range.start()
+        } else {
+            // this range's start and the last one's end can both move toward each other
+            (covered_to + 1 - range.start()) / 2
+        });
+        covered_to = *range.end();
+        if covered_to >= bound as i64 {
+            return RowSearch::CoveredFor(skip.min(covered_to - bound as i64));
+        }
+    }
+
+    // short of the bound, so it's still an i32
+    RowSearch::Gap(covered_to as i32 + 1)
+}
+
+// the first position, going row by row, from (0, 0) through (bound, bound), that none of the
+// `sensors` covers
+fn first_gap(sensors: &[Sensor], bound: i32) -> Option<Point2> {
+    // skipping rows can go past the end of an i32, but it's still short of the end of an i64
+    let mut y: i64 = 0;
+    while y <= bound as i64 {
+        match search_row(sensors, y as i32, bound) {
+            RowSearch::Gap(x) => return Some(Point2::new(x, y as i32)),
+            RowSearch::CoveredFor(rows) => y += rows + 1,
+        }
+    }
+    None
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 30;
+
+    /// `size` sensors scattered around the search area (at least four of them), and however many
+    /// more it takes so that there's only one position left in it for the distress beacon.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let bound = Config::default().search_bound;
+        let distress = Point2::new(rng.gen_range(0..=bound), rng.gen_range(0..=bound));
+
+        // four sensors diagonally around it, which between them cover everything near it
+        let mut sensors = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+            .into_iter()
+            .map(|(dx, dy)| random_sensor(rng, distress, distress + Point2::new(dx, dy) * 2, 3))
+            .collect_vec();
+
+        while sensors.len() < size {
+            let position = Point2::new(rng.gen_range(0..=bound), rng.gen_range(0..=bound));
+            if position != distress {
+                let distance = position.manhattan(distress);
+                sensors.push(random_sensor(
+                    rng,
+                    distress,
+                    position,
+                    distance / 2 + distance / 4,
+                ));
+            }
+        }
+
+        // and then a sensor in every gap that's left, seeing as close to the distress beacon as
+        // it can, which is the one thing that's meant to be left uncovered
+        let mut search = sensors.clone();
+        search.push(Sensor {
+            position: distress,
+            beacon: distress,
+        });
+        while let Some(gap) = first_gap(&search, bound) {
+            let gap_sensor = random_sensor(rng, distress, gap, i32::MAX);
+            sensors.push(gap_sensor);
+            search.push(gap_sensor);
+        }
+
+        sensors.shuffle(rng);
+        sensors
+            .into_iter()
+            .map(|sensor| {
+                format!(
+                    "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+                    sensor.position.x, sensor.position.y, sensor.beacon.x, sensor.beacon.y
+                )
+            })
+            .collect()
+    }
+}
+
+// a sensor at `position`, that can see as far as `radius` but stops short of the `distress` beacon,
+// and sees its own beacon somewhere at the edge of that
+fn random_sensor(rng: &mut impl Rng, distress: Point2, position: Point2, radius: i32) -> Sensor {
+    let radius = radius.min(position.manhattan(distress) - 1);
+    let dx = rng.gen_range(-radius..=radius);
+    let dy = (radius - dx.abs()) * if rng.gen() { 1 } else { -1 };
+    Sensor {
+        position,
+        beacon: position + Point2::new(dx, dy),
+    }
+}
+
+prelude::example_tests!(Config::example());
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn example_row() {
+        let solution = Solution::with_config(EXAMPLE, Config::example()).unwrap();
+        assert_eq!(solution.row_coverage(10), vec![-2..=24]);
+        // the row with the distress beacon in it, at x=14
+        assert_eq!(solution.row_coverage(11), vec![-3..=13, 15..=25]);
+    }
+
+    #[test]
+    fn corner_to_corner() {
+        // the sensor can see further than an i32 goes, and covers everything
+        let config = Config {
+            row: i32::MIN,
+            ..Config::default()
+        };
+        let solution = Solution::with_config(
+            &format!(
+                "Sensor at x={min}, y={min}: closest beacon is at x={max}, y={max}\n",
+                min = i32::MIN,
+                max = i32::MAX,
+            ),
+            config,
+        )
+        .unwrap();
+        // reaching 2^33 - 2 either way along its own row
+        assert_eq!(solution.part1(), Ok((1 << 34) - 3));
+        assert!(solution.part2().is_err());
+    }
+
+    fn sensors() -> impl Strategy<Value = Vec<Sensor>> {
+        let point = || (-5..25, -5..25).prop_map(|(x, y)| Point2::new(x, y));
+        prop::collection::vec(
+            (point(), point()).prop_map(|(position, beacon)| Sensor { position, beacon }),
+            0..8,
+        )
+    }
+
+    fn covers(sensor: &Sensor, point: Point2) -> bool {
+        sensor.position.manhattan(point) as i64 <= sensor.radius()
+    }
+
+    proptest! {
+        #[test]
+        fn same_as_cells(sensors in sensors(), y in -5..25) {
+            let covered = |x: i32| {
+                let point = Point2::new(x, y);
+                sensors.iter().any(|sensor| covers(sensor, point))
+            };
+
+            let solution = Solution { sensors: sensors.clone(), config: Config::example() };
+            let merged = solution.row_coverage(y);
+            for x in -60..90 {
+                let in_merged = merged.iter().any(|range| range.contains(&(x as i64)));
+                prop_assert_eq!(in_merged, covered(x));
+            }
+
+            // and the search, skipping rows, finds the same first gap as looking everywhere
+            let expected = (0..=20)
+                .cartesian_product(0..=20)
+                .map(|(y, x)| Point2::new(x, y))
+                .find(|&point| !sensors.iter().any(|sensor| covers(sensor, point)));
+            prop_assert_eq!(first_gap(&sensors, 20), expected);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::{hash_map, BTreeSet};
+use std::fmt::{self, Display};
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
@@ -6,9 +7,16 @@ use nom::character::complete::alpha1;
 use nom::multi::separated_list1;
 use nom::IResult;
 use prelude::log::{debug, info};
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i32 = 1651;
+pub const EXAMPLE_PART2: i32 = 1707;
+
+#[derive(Debug, PartialEq, Serialize)]
 struct Valve {
     flow_rate: u32,
     neighbors: Vec<String>,
@@ -44,29 +52,114 @@ fn parse_valve(input: &str) -> IResult<&str, (String, Valve)> {
     ))
 }
 
+/// Where we start, and how long we have to release pressure: alone in part 1, or with an elephant
+/// after teaching it what to do in part 2.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub start: String,
+    pub part1_minutes: i32,
+    pub part2_minutes: i32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            start: "AA".to_owned(),
+            part1_minutes: 30,
+            part2_minutes: 26,
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct Solution {
     valves: HashMap<String, Valve>,
+    #[serde(skip)]
+    config: Config,
+}
+
+/// Writes the valves back out the way the input had them, in order by name.
+impl Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, valve) in self.valves.iter().sorted_by_key(|&(name, _)| name) {
+            let tunnels = match valve.neighbors.len() {
+                1 => "tunnel leads to valve",
+                _ => "tunnels lead to valves",
+            };
+            writeln!(
+                f,
+                "Valve {} has flow rate={}; {} {}",
+                name,
+                valve.flow_rate,
+                tunnels,
+                valve.neighbors.iter().join(", ")
+            )?;
+        }
+        Ok(())
+    }
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
         init();
 
-        let valves = input
+        let parsed = input
             .lines()
-            .map(|line| {
-                let (rest, valve) = parse_valve(line).expect("invalid input");
-                assert!(rest.is_empty());
-                valve
+            .enumerate()
+            .map(|(i, line)| {
+                Ok((
+                    i + 1,
+                    line,
+                    parse_line(i + 1, line, "a valve", parse_valve)?,
+                ))
             })
-            .collect();
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
+        // every tunnel has to lead somewhere, and we have to start from somewhere
+        let names = parsed
+            .iter()
+            .map(|(_, _, (name, _))| name.as_str())
+            .collect::<HashSet<_>>();
+        for (line_number, line, (_, valve)) in &parsed {
+            if let Some(neighbor) = valve.neighbors.iter().find(|n| !names.contains(n.as_str())) {
+                // the neighbors are the last thing on the line
+                let position = line.rfind(neighbor.as_str()).unwrap();
+                return Err(ParseError::at(
+                    *line_number,
+                    line,
+                    &line[position..],
+                    "the name of a valve",
+                ));
+            }
+        }
+        if !names.contains(config.start.as_str()) {
+            return Err(ParseError::end_of_input(
+                parsed.len() + 1,
+                format!("a valve named {}", config.start),
+            ));
+        }
+
+        let valves: HashMap<_, _> = parsed.into_iter().map(|(_, _, valve)| valve).collect();
 
         debug!("parsed: {:#?}", valves);
 
-        Solution { valves }
+        Ok(Solution { valves, config })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = i32;
+    type Part2 = i32;
+    type Error = Cancelled;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
     }
 
-    pub fn part1(&self) -> i32 {
+    fn part1(&self) -> Result<i32, Cancelled> {
         let mut graph = petgraph::graphmap::DiGraphMap::new();
         for (name, valve) in &self.valves {
             for target in &valve.neighbors {
@@ -83,17 +176,17 @@ impl Solution {
             .filter_map(|(name, valve)| (valve.flow_rate > 0).then_some(name.as_str()))
             .collect();
 
-        max_flow_after_visiting(
-            30,
-            "AA",
+        Ok(max_flow_after_visiting(
+            self.config.part1_minutes,
+            &self.config.start,
             &nonzero_valves,
             &self.valves,
             &distances,
             &mut Default::default(),
-        )
+        ))
     }
 
-    pub fn part2(&self) -> i32 {
+    fn part2(&self) -> Result<i32, Cancelled> {
         let mut graph = petgraph::graphmap::DiGraphMap::new();
         for (name, valve) in &self.valves {
             for target in &valve.neighbors {
@@ -112,13 +205,22 @@ impl Solution {
 
         info!("nonzero valves: {}", nonzero_valves.len());
 
+        // with nothing worth opening there's nobody to split the work between, and no pressure
+        // to release either
+        if nonzero_valves.is_empty() {
+            return Ok(0);
+        }
+
         let mut max_flow = 0;
 
         // choose that "myself" will visit the highest-order valve, without loss of generality; a
         // zero in one bit represents that "myself" will take it, a one in a bit represents that
         // "elephant" will take it
         let mut memoized = Default::default();
-        for i in 0..(1 << (nonzero_valves.len() - 1)) {
+        let partitions = 1 << (nonzero_valves.len() - 1);
+        for i in 0..partitions {
+            progress::report(i, Some(partitions))?;
+
             let mut myself = BTreeSet::new();
             let mut elephant = BTreeSet::new();
 
@@ -132,15 +234,15 @@ impl Solution {
                 max_flow = std::cmp::max(
                     max_flow,
                     max_flow_after_visiting(
-                        26,
-                        "AA",
+                        self.config.part2_minutes,
+                        &self.config.start,
                         &myself,
                         &self.valves,
                         &distances,
                         &mut memoized,
                     ) + max_flow_after_visiting(
-                        26,
-                        "AA",
+                        self.config.part2_minutes,
+                        &self.config.start,
                         &elephant,
                         &self.valves,
                         &distances,
@@ -150,7 +252,7 @@ impl Solution {
             }
         }
 
-        max_flow
+        Ok(max_flow)
     }
 }
 
@@ -162,7 +264,7 @@ fn max_flow_after_visiting<'a>(
     distances: &HashMap<(&str, &str), i32>,
     memoized: &mut HashMap<(i32, &'a str, BTreeSet<&'a str>), i32>,
 ) -> i32 {
-    // if we took longer than 30 minutes to get to this node, then this doesn't count as a maximum
+    // if we took longer than we had to get to this node, then this doesn't count as a maximum
     // path at all
     if time_remaining < 0 {
         return 0;
@@ -177,12 +279,18 @@ fn max_flow_after_visiting<'a>(
         hash_map::Entry::Occupied(occupied) => return *occupied.get(),
         hash_map::Entry::Vacant(vacant) => vacant.into_key().2,
     };
+    instrument::count("states explored", 1);
 
     let mut max_released = 0;
 
     for &next in &to_visit {
+        // floyd_warshall leaves i32::MAX between valves with no way from one to the other
+        let distance = distances[&(location, next)];
+        if distance == i32::MAX {
+            continue;
+        }
         // subtract 1 minute to account for turning `next` on.
-        let time_remaining = time_remaining - distances[&(location, next)] - 1;
+        let time_remaining = time_remaining - distance - 1;
         max_released = std::cmp::max(
             max_released,
             (time_remaining * valves[next].flow_rate as i32)
@@ -202,3 +310,235 @@ fn max_flow_after_visiting<'a>(
     memoized.insert((time_remaining, location, to_visit), max_released);
     max_released
 }
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 60;
+
+    /// `size` valves (up to 676, since they're named with two letters), about a quarter of them
+    /// with any flow, all connected to AA.  Part 2 takes twice as long for every valve with flow,
+    /// so this gets slow quickly.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let count = size.clamp(2, 26 * 26);
+        let mut names = (b'A'..=b'Z')
+            .cartesian_product(b'A'..=b'Z')
+            .map(|(first, second)| String::from_utf8(vec![first, second]).unwrap())
+            .filter(|name| name != "AA")
+            .collect_vec();
+        names.shuffle(rng);
+        names.truncate(count - 1);
+        names.insert(0, "AA".to_owned());
+
+        // each valve hooks up to one that came before it, so they're all connected, and then
+        // there are a few more tunnels here and there
+        let mut neighbors = vec![BTreeSet::new(); count];
+        let mut connect = |a: usize, b: usize| {
+            if a != b {
+                neighbors[a].insert(b);
+                neighbors[b].insert(a);
+            }
+        };
+        for i in 1..count {
+            connect(i, rng.gen_range(0..i));
+        }
+        for _ in 0..count / 4 {
+            connect(rng.gen_range(0..count), rng.gen_range(0..count));
+        }
+
+        let mut flowing = (1..count).collect_vec();
+        flowing.shuffle(rng);
+        flowing.truncate((count / 4).max(1));
+
+        let mut lines = (0..count)
+            .map(|i| {
+                let flow_rate = if flowing.contains(&i) {
+                    rng.gen_range(1..=25)
+                } else {
+                    0
+                };
+                let tunnels = match neighbors[i].len() {
+                    1 => "tunnel leads to valve",
+                    _ => "tunnels lead to valves",
+                };
+                format!(
+                    "Valve {} has flow rate={}; {} {}\n",
+                    names[i],
+                    flow_rate,
+                    tunnels,
+                    neighbors[i].iter().map(|&n| &names[n]).join(", ")
+                )
+            })
+            .collect_vec();
+        lines.shuffle(rng);
+
+        lines.concat()
+    }
+}
+
+// the slow way: every minute, whoever's looking for valves either opens the one they're at or walks
+// down one of its tunnels, trying every combination, instead of working out the distances between
+// the valves that matter and dividing them up
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    // the valves by number, so the ones that are open can be a bitmask
+    struct Cave {
+        flow_rates: Vec<i32>,
+        neighbors: Vec<Vec<usize>>,
+    }
+
+    // what one of us can do with a minute: open a valve, or go somewhere else
+    #[derive(Clone, Copy, PartialEq)]
+    enum Action {
+        Open(usize),
+        Walk(usize),
+    }
+
+    impl Cave {
+        fn actions(&self, at: usize, open: u64) -> impl Iterator<Item = Action> + '_ {
+            let can_open = self.flow_rates[at] > 0 && open & (1 << at) == 0;
+            can_open
+                .then_some(Action::Open(at))
+                .into_iter()
+                .chain(self.neighbors[at].iter().map(|&n| Action::Walk(n)))
+        }
+
+        // the most pressure that can be released in the minutes left, by everyone at `at`
+        fn most_released(
+            &self,
+            minutes: i32,
+            at: &[usize],
+            open: u64,
+            memoized: &mut HashMap<(i32, Vec<usize>, u64), i32>,
+        ) -> i32 {
+            if minutes == 0 {
+                return 0;
+            }
+            if let Some(&released) = memoized.get(&(minutes, at.to_vec(), open)) {
+                return released;
+            }
+
+            let mut most = 0;
+            for actions in at
+                .iter()
+                .map(|&a| self.actions(a, open).collect_vec())
+                .multi_cartesian_product()
+            {
+                // two of us can't open the same valve in the same minute
+                if actions
+                    .iter()
+                    .tuple_combinations()
+                    .any(|(a, b)| a == b && matches!(a, Action::Open(_)))
+                {
+                    continue;
+                }
+
+                let (mut open, mut released, mut next) = (open, 0, vec![]);
+                for action in actions {
+                    match action {
+                        Action::Open(valve) => {
+                            open |= 1 << valve;
+                            released += self.flow_rates[valve] * (minutes - 1);
+                            next.push(valve);
+                        }
+                        Action::Walk(to) => next.push(to),
+                    }
+                }
+                most = most.max(released + self.most_released(minutes - 1, &next, open, memoized));
+            }
+
+            memoized.insert((minutes, at.to_vec(), open), most);
+            most
+        }
+    }
+
+    impl Solution {
+        fn most_released(&self, minutes: i32, people: usize) -> i32 {
+            let names = self.valves.keys().sorted().collect_vec();
+            assert!(names.len() <= 64, "too many valves to keep track of");
+            let index = |name: &String| names.iter().position(|&n| n == name).unwrap();
+
+            let cave = Cave {
+                flow_rates: names
+                    .iter()
+                    .map(|&name| self.valves[name].flow_rate as i32)
+                    .collect(),
+                neighbors: names
+                    .iter()
+                    .map(|&name| self.valves[name].neighbors.iter().map(index).collect())
+                    .collect(),
+            };
+            let start = vec![index(&self.config.start); people];
+            cave.most_released(minutes, &start, 0, &mut HashMap::new())
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i32, Cancelled> {
+            Ok(self.most_released(self.config.part1_minutes, 1))
+        }
+
+        fn reference_part2(&self) -> Result<i32, Cancelled> {
+            Ok(self.most_released(self.config.part2_minutes, 2))
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(10);
+prelude::parser_tests!(10);
+prelude::differential_tests!(12);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn round_trip(seed: u64) {
+            let solution = Solution::new(&Solution::generate_seeded(seed, 10)).unwrap();
+            let reparsed = Solution::new(&solution.to_string()).unwrap();
+            prop_assert_eq!(reparsed.valves, solution.valves);
+        }
+
+        #[test]
+        fn elephant_never_hurts(seed: u64) {
+            // the elephant could always just stand there, leaving us to do what we'd do alone
+            let config = Config {
+                part1_minutes: 26,
+                ..Config::default()
+            };
+            let solution =
+                Solution::with_config(&Solution::generate_seeded(seed, 10), config).unwrap();
+            prop_assert!(solution.part2().unwrap() >= solution.part1().unwrap());
+        }
+    }
+
+    #[test]
+    fn no_flow() {
+        let solution = Solution::new(
+            "Valve AA has flow rate=0; tunnels lead to valves BB\n\
+             Valve BB has flow rate=0; tunnel leads to valve AA\n",
+        )
+        .unwrap();
+        assert_eq!(solution.part1().unwrap(), 0);
+        assert_eq!(solution.part2().unwrap(), 0);
+    }
+
+    #[test]
+    fn out_of_reach() {
+        // CC has a tunnel to AA, but there's none back
+        let solution = Solution::new(
+            "Valve AA has flow rate=0; tunnel leads to valve BB\n\
+             Valve BB has flow rate=3; tunnel leads to valve AA\n\
+             Valve CC has flow rate=5; tunnel leads to valve AA\n",
+        )
+        .unwrap();
+        assert_eq!(solution.part1().unwrap(), 28 * 3);
+        assert_eq!(solution.part2().unwrap(), 24 * 3);
+        assert_eq!(solution.reference_part1().unwrap(), 28 * 3);
+        assert_eq!(solution.reference_part2().unwrap(), 24 * 3);
+    }
+}
@@ -3,10 +3,16 @@ use std::collections::BTreeSet;
 use std::ops::ControlFlow;
 
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::{Deserialize, Serialize};
 
 use RockType::*;
 
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u64 = 3068;
+pub const EXAMPLE_PART2: u64 = 1514285714288;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum RockType {
     Underscore,
@@ -20,17 +26,17 @@ enum RockType {
 struct Rock {
     rock_type: RockType,
     left_pos: u32,
-    bottom_pos: u32,
+    bottom_pos: u64,
 }
 
 impl Rock {
-    fn move_left(&mut self, chamber: &HashSet<(u32, u32)>) {
+    fn move_left(&mut self, chamber: &HashSet<(u32, u64)>) {
         if self.left_pos == 0 {
             return;
         }
 
-        // offsets from (left_pos - 1, bottom_pos), so we can do all the math as u32
-        let left_offsets: &[(u32, u32)] = match self.rock_type {
+        // offsets from (left_pos - 1, bottom_pos), so we can do all the math unsigned
+        let left_offsets: &[(u32, u64)] = match self.rock_type {
             Underscore => &[(0, 0)],
             Plus => &[(1, 0), (0, 1), (1, 2)],
             Ell => &[(0, 0), (2, 1), (2, 2)],
@@ -48,7 +54,7 @@ impl Rock {
         self.left_pos -= 1;
     }
 
-    fn move_right(&mut self, chamber: &HashSet<(u32, u32)>) {
+    fn move_right(&mut self, chamber: &HashSet<(u32, u64)>, chamber_width: u32) {
         let width = match self.rock_type {
             Underscore => 4,
             Plus | Ell => 3,
@@ -56,13 +62,13 @@ impl Rock {
             Square => 2,
         };
 
-        if self.left_pos + width == 7 {
+        if self.left_pos + width == chamber_width {
             return;
         }
-        assert!(self.left_pos + width < 7);
+        assert!(self.left_pos + width < chamber_width);
 
         // offsets from (left_pos, bottom_pos), thankfully, since we can just add unsigned numbers
-        let offsets: &[(u32, u32)] = match self.rock_type {
+        let offsets: &[(u32, u64)] = match self.rock_type {
             Underscore => &[(4, 0)],
             Plus => &[(2, 2), (3, 1), (2, 0)],
             Ell => &[(3, 2), (3, 1), (3, 0)],
@@ -80,15 +86,15 @@ impl Rock {
         self.left_pos += 1;
     }
 
-    // Break(u32) represents one-past-the-top of the height of the brick, if it ossifies.
-    fn move_down(&mut self, chamber: &mut HashSet<(u32, u32)>) -> ControlFlow<u32> {
+    // Break(u64) represents one-past-the-top of the height of the brick, if it ossifies.
+    fn move_down(&mut self, chamber: &mut HashSet<(u32, u64)>) -> ControlFlow<u64> {
         if self.bottom_pos == 0 {
             return self.ossify(chamber);
         }
 
-        // offsets from the row *below* this rock, so we can continue working in u32s, in
+        // offsets from the row *below* this rock, so we can continue working unsigned, in
         // (horizontal, vertical) order.
-        let below_offsets: &[(u32, u32)] = match self.rock_type {
+        let below_offsets: &[(u32, u64)] = match self.rock_type {
             Underscore => &[(0, 0), (1, 0), (2, 0), (3, 0)],
             Plus => &[(0, 1), (1, 0), (2, 1)],
             Ell => &[(0, 0), (1, 0), (2, 0)],
@@ -107,9 +113,9 @@ impl Rock {
         ControlFlow::Continue(())
     }
 
-    fn ossify(&self, chamber: &mut HashSet<(u32, u32)>) -> ControlFlow<u32> {
+    fn ossify(&self, chamber: &mut HashSet<(u32, u64)>) -> ControlFlow<u64> {
         // offsets from (left_pos, bottom_pos)
-        let offsets: &[(u32, u32)] = match self.rock_type {
+        let offsets: &[(u32, u64)] = match self.rock_type {
             Underscore => &[(0, 0), (1, 0), (2, 0), (3, 0)],
             Plus => &[(0, 1), (1, 2), (1, 1), (1, 0), (2, 1)],
             Ell => &[(0, 0), (1, 0), (2, 2), (2, 1), (2, 0)],
@@ -132,162 +138,330 @@ impl Rock {
     }
 }
 
-pub struct Solution {
-    input: String,
+// the rocks fall in this order, over and over again
+const ROCK_TYPES: [RockType; 5] = [Underscore, Plus, Ell, Pipe, Square];
+
+// everything that decides where the following rocks will land: the next jet and rock, and the open
+// space at the top that a rock could still fall into, even down some narrow gap
+type Fingerprint = (usize, usize, BTreeSet<(u32, u64)>);
+
+// how far down the open space goes into the fingerprint
+const FINGERPRINT_DEPTH: u64 = 50;
+
+/// The rocks at rest in the chamber, which they fall into one at a time as the jets push them
+/// around.  There are always more rocks, so it's never done.
+pub struct Chamber<'a> {
+    jets: &'a [u8],
+    width: u32,
+    next_jet: usize,
+    next_rock: usize,
+    rocks: HashSet<(u32, u64)>,
+    height: u64,
+    dropped: u64,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
-        init();
-
-        Solution {
-            input: input.to_owned(),
+impl<'a> Chamber<'a> {
+    fn new(jets: &'a str, width: u32) -> Self {
+        Chamber {
+            jets: jets.as_bytes(),
+            width,
+            next_jet: 0,
+            next_rock: 0,
+            rocks: HashSet::new(),
+            height: 0,
+            dropped: 0,
         }
     }
 
-    pub fn part1(&self) -> u32 {
-        let mut count = 0;
-        let mut next_rock_type = [Underscore, Plus, Ell, Pipe, Square].into_iter().cycle();
-        let mut input = self.input.chars().cycle();
-        let mut chamber = HashSet::new();
-        let mut max_height = 0;
+    /// One past the top of the highest rock.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// How many rocks have come to rest.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
 
+    // lets the next rock fall until it comes to rest
+    fn drop_rock(&mut self) {
         let mut rock = Rock {
-            rock_type: next_rock_type.next().unwrap(),
+            rock_type: ROCK_TYPES[self.next_rock],
             left_pos: 2,
-            bottom_pos: 3,
+            bottom_pos: self.height + 3,
         };
+        self.next_rock = (self.next_rock + 1) % ROCK_TYPES.len();
 
         loop {
-            match input.next().expect("ran out of input") {
-                '<' => rock.move_left(&chamber),
-                '>' => rock.move_right(&chamber),
-                x => panic!("unexpected character {x:?}"),
+            match self.jets[self.next_jet] {
+                b'<' => rock.move_left(&self.rocks),
+                b'>' => rock.move_right(&self.rocks, self.width),
+                x => panic!("unexpected character {:?}", x as char),
             }
+            self.next_jet = (self.next_jet + 1) % self.jets.len();
 
-            match rock.move_down(&mut chamber) {
-                ControlFlow::Continue(()) => (),
-                ControlFlow::Break(top) => {
-                    max_height = max(max_height, top);
-                    count += 1;
-                    if count == 2022 {
-                        return max_height;
-                    }
-
-                    rock = Rock {
-                        rock_type: next_rock_type.next().unwrap(),
-                        left_pos: 2,
-                        bottom_pos: max_height + 3,
-                    };
-                }
+            if let ControlFlow::Break(top) = rock.move_down(&mut self.rocks) {
+                self.height = max(self.height, top);
+                self.dropped += 1;
+                instrument::count("rocks dropped", 1);
+                return;
             }
         }
     }
 
-    pub fn part2(&self) -> u64 {
-        let mut count = 0;
-        let mut next_rock_type = [Underscore, Plus, Ell, Pipe, Square].into_iter().cycle();
-        let mut input = self.input.chars().enumerate().cycle();
-        let mut chamber = HashSet::new();
-        let mut max_height = 0;
+    fn fingerprint(&self) -> Option<Fingerprint> {
+        // flood down from the empty row above the top, the way a falling rock can move; depths are
+        // counted down from that row, so they line up with the same shape at any height.  It stops
+        // at some point, since a column that never fills up would keep the flood from ever
+        // repeating itself, and whatever falls that far down is very unlikely to matter up top.
+        let mut open = BTreeSet::new();
+        let mut to_visit = (0..self.width).map(|column| (column, 0)).collect_vec();
+        while let Some((column, depth)) = to_visit.pop() {
+            if self.rocks.contains(&(column, self.height - depth)) || !open.insert((column, depth))
+            {
+                continue;
+            }
 
-        // (last rock placed, top 10 rows with the indexes shifted down to the "floor") -> height of
-        // the first time we saw that situation
-        let mut seen = HashMap::new();
+            if column > 0 {
+                to_visit.push((column - 1, depth));
+            }
+            if column + 1 < self.width {
+                to_visit.push((column + 1, depth));
+            }
+            if depth < self.height.min(FINGERPRINT_DEPTH) {
+                to_visit.push((column, depth + 1));
+            }
+        }
 
-        let mut rock = Rock {
-            rock_type: next_rock_type.next().unwrap(),
-            left_pos: 2,
-            bottom_pos: 3,
-        };
+        Some((self.next_jet, self.next_rock, open))
+    }
+}
 
-        let cycle_base;
-        let cycle_start;
+impl Simulation for Chamber<'_> {
+    /// Every (column, row) filled by a rock, with row 0 at the floor.
+    type State = HashSet<(u32, u64)>;
 
-        loop {
-            let (i, direction) = input.next().unwrap();
-            match direction {
-                '<' => rock.move_left(&chamber),
-                '>' => rock.move_right(&chamber),
-                x => panic!("unexpected character {x:?}"),
-            }
+    fn step(&mut self) {
+        self.drop_rock();
+    }
 
-            match rock.move_down(&mut chamber) {
-                ControlFlow::Continue(()) => (),
-                ControlFlow::Break(top) => {
-                    max_height = max(max_height, top);
-                    count += 1;
-
-                    if max_height > 10 {
-                        let top_ten_rows: BTreeSet<(u32, u32)> = chamber
-                            .iter()
-                            .filter_map(|&(left, up)| {
-                                if up > max_height - 10 {
-                                    Some((left, up + 10 - max_height))
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-
-                        if let Some(&(old_count, old_height)) =
-                            seen.get(&(i, rock.rock_type, top_ten_rows.clone()))
-                        {
-                            log::info!("cycle after {count} rocks at height {max_height}, previous count {old_count}, height {old_height}!");
-                            cycle_start = old_count;
-                            cycle_base = old_height;
-                            break;
-                        }
-
-                        seen.insert((i, rock.rock_type, top_ten_rows), (count, max_height));
-                    }
-
-                    rock = Rock {
-                        rock_type: next_rock_type.next().unwrap(),
-                        left_pos: 2,
-                        bottom_pos: max_height + 3,
-                    };
-                }
-            }
+    fn state(&self) -> &HashSet<(u32, u64)> {
+        &self.rocks
+    }
+
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+/// How many rocks fall in each part, and how wide the chamber is.  Rocks appear two units from its
+/// left wall, so it has to be at least six wide to fit them all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub part1_rocks: u64,
+    pub part2_rocks: u64,
+    pub width: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            part1_rocks: 2022,
+            part2_rocks: 1_000_000_000_000,
+            width: 7,
         }
+    }
+}
 
-        let cycle_count = count - cycle_start;
-        let final_cycle_height = max_height;
-        let cycle_height = max_height - cycle_base;
+#[derive(Serialize)]
+pub struct Solution {
+    input: String,
+    #[serde(skip)]
+    config: Config,
+}
 
-        rock = Rock {
-            rock_type: next_rock_type.next().unwrap(),
-            left_pos: 2,
-            bottom_pos: max_height + 3,
-        };
+impl Configurable for Solution {
+    type Config = Config;
 
-        let mut count = 0;
-        let target_count = 1000000000000u64 - cycle_start;
-        loop {
-            match input.next().expect("ran out of input").1 {
-                '<' => rock.move_left(&chamber),
-                '>' => rock.move_right(&chamber),
-                x => panic!("unexpected character {x:?}"),
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
+        init();
+
+        if config.width < 6 {
+            return Err(ParseError::config(
+                "a chamber at least 6 wide",
+                format!("a width of {}", config.width),
+            ));
+        }
+
+        // the jets are all on the first line, and we'd just cycle through anything else forever
+        let mut lines = input.lines();
+        let jets = lines.next().unwrap_or("");
+        if let Some(position) = jets.find(|c| c != '<' && c != '>') {
+            return Err(ParseError::at(1, jets, &jets[position..], "< or >"));
+        }
+        if jets.is_empty() {
+            return Err(ParseError::end_of_line(1, jets, "< or >"));
+        }
+        if let Some((i, line)) = lines.enumerate().find(|(_, line)| !line.is_empty()) {
+            return Err(ParseError::at(i + 2, line, line, "end of input"));
+        }
+
+        Ok(Solution {
+            input: jets.to_owned(),
+            config,
+        })
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = u64;
+    type Part2 = u64;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
+
+    fn part1(&self) -> Result<u64, String> {
+        let mut chamber = self.chamber();
+        while chamber.dropped() < self.config.part1_rocks {
+            chamber.drop_rock();
+            if chamber.dropped.is_multiple_of(REPORT_EVERY) {
+                progress::report(chamber.dropped, Some(self.config.part1_rocks))
+                    .map_err(|cancelled| cancelled.to_string())?;
             }
+        }
+
+        Ok(chamber.height())
+    }
 
-            match rock.move_down(&mut chamber) {
-                ControlFlow::Continue(()) => (),
-                ControlFlow::Break(top) => {
-                    max_height = max(max_height, top);
-                    count += 1;
-                    if count == target_count % (cycle_count as u64) {
-                        return (cycle_height as u64) * (target_count / cycle_count as u64)
-                            + (max_height as u64 - final_cycle_height as u64)
-                            + cycle_base as u64;
-                    }
-
-                    rock = Rock {
-                        rock_type: next_rock_type.next().unwrap(),
-                        left_pos: 2,
-                        bottom_pos: max_height + 3,
-                    };
+    fn part2(&self) -> Result<u64, String> {
+        let height = cycle::value_after(
+            self.chamber(),
+            self.config.part2_rocks,
+            |chamber| {
+                chamber.drop_rock();
+                // there's no telling how long it'll take to find a cycle, and dropping one rock is
+                // quicker than calling out to say so
+                if chamber.dropped.is_multiple_of(REPORT_EVERY) {
+                    progress::report(chamber.dropped, None)?;
                 }
+                Ok(())
+            },
+            Chamber::fingerprint,
+            // it'd take longer than anyone could wait to drop enough rocks to get past an i64
+            |chamber| chamber.height as i64,
+        )
+        .map_err(|cancelled: Cancelled| cancelled.to_string())?
+        .ok_or_else(|| "the tower gets too tall to measure".to_owned())?;
+
+        Ok(height as u64)
+    }
+}
+
+// how many rocks each part drops between reports of how it's going
+const REPORT_EVERY: u64 = 1000;
+
+impl Solution {
+    /// The empty chamber, before any rocks have fallen.
+    pub fn chamber(&self) -> Chamber<'_> {
+        Chamber::new(&self.input, self.config.width)
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 10_000;
+
+    /// `size` jets of hot gas.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| if rng.gen() { '<' } else { '>' })
+            .chain(['\n'])
+            .collect()
+    }
+}
+
+// the slow way: drops every last rock, rather than looking for the point where the chamber starts
+// repeating itself
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u64, String> {
+            Ok(self.height_after(self.config.part1_rocks))
+        }
+
+        fn reference_part2(&self) -> Result<u64, String> {
+            Ok(self.height_after(self.config.part2_rocks))
+        }
+    }
+
+    impl Solution {
+        fn height_after(&self, rocks: u64) -> u64 {
+            let mut chamber = self.chamber();
+            while chamber.dropped() < rocks {
+                chamber.drop_rock();
             }
+            chamber.height()
         }
     }
 }
+
+prelude::example_tests!();
+prelude::generator_tests!(40);
+prelude::parser_tests!(40);
+prelude::differential_tests!(
+    40,
+    Config {
+        part2_rocks: 5000,
+        ..Config::default()
+    }
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn narrow_chamber() {
+        let config = Config {
+            width: 5,
+            ..Config::default()
+        };
+        assert!(Solution::with_config(EXAMPLE, config).is_err());
+
+        // just wide enough for the flat rock, two from the left wall
+        let config = Config {
+            width: 6,
+            ..Config::default()
+        };
+        let solution = Solution::with_config(EXAMPLE, config).unwrap();
+        assert!(solution.part1().is_ok());
+    }
+
+    #[test]
+    fn too_tall() {
+        let config = Config {
+            part2_rocks: u64::MAX,
+            ..Config::default()
+        };
+        let solution = Solution::with_config(EXAMPLE, config).unwrap();
+        assert!(solution.part2().is_err());
+    }
+
+    #[test]
+    fn cancelled() {
+        // far more rocks than could ever be dropped one by one, so it has to stop when it's told to
+        let config = Config {
+            part1_rocks: u64::MAX,
+            ..Config::default()
+        };
+        let solution = Solution::with_config(EXAMPLE, config).unwrap();
+        let token = progress::CancellationToken::new();
+        token.cancel();
+        let result = progress::with_progress(&token, |_| (), || solution.part1());
+        assert_eq!(result, Err(progress::Cancelled.to_string()));
+    }
+}
@@ -1,178 +1,294 @@
-use std::collections::VecDeque;
+use std::convert::Infallible;
 
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: u32 = 64;
+pub const EXAMPLE_PART2: u32 = 58;
+
+// far bigger than any droplet the puzzle has, and far enough from the ends of an i32 that stepping
+// around the box the droplet is in can't overflow
+const COORDINATE_LIMIT: i32 = 1_000_000;
+
+#[derive(Serialize)]
 pub struct Solution {
-    cubes: HashSet<(i32, i32, i32)>,
+    cubes: HashSet<Point3>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+impl prelude::Solution for Solution {
+    type Part1 = u32;
+    type Part2 = u32;
+    type Error = Infallible;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
         let cubes = input
             .lines()
-            .map(|line| {
-                let split = line.split(',').collect_vec();
-                (
-                    split[0].parse().unwrap(),
-                    split[1].parse().unwrap(),
-                    split[2].parse().unwrap(),
-                )
+            .enumerate()
+            .map(|(i, line)| {
+                let mut split = line.split(',');
+                let mut coordinate = || {
+                    let word = split
+                        .next()
+                        .ok_or_else(|| ParseError::end_of_line(i + 1, line, "a coordinate"))?;
+                    let coordinate: i32 = parse_word(i + 1, line, word, "a coordinate")?;
+                    if coordinate.abs() > COORDINATE_LIMIT {
+                        return Err(ParseError::at(
+                            i + 1,
+                            line,
+                            word,
+                            format!(
+                                "a coordinate from -{} to {}",
+                                COORDINATE_LIMIT, COORDINATE_LIMIT
+                            ),
+                        ));
+                    }
+                    Ok(coordinate)
+                };
+                let cube = Point3::new(coordinate()?, coordinate()?, coordinate()?);
+                if let Some(extra) = split.next() {
+                    return Err(ParseError::at(i + 1, line, extra, "end of line"));
+                }
+                Ok(cube)
             })
-            .collect();
+            .collect::<Result<HashSet<_>, _>>()?;
+        if cubes.is_empty() {
+            return Err(ParseError::end_of_input(
+                input.lines().count() + 1,
+                "at least one cube",
+            ));
+        }
 
         debug!("parsed {cubes:#?}");
 
-        Solution { cubes }
+        Ok(Solution { cubes })
     }
 
-    pub fn part1(&self) -> u32 {
+    fn part1(&self) -> Result<u32, Infallible> {
         let mut result = 0;
 
-        for &(i, j, k) in &self.cubes {
-            for (a, b, c) in [
-                (i - 1, j, k),
-                (i + 1, j, k),
-                (i, j - 1, k),
-                (i, j + 1, k),
-                (i, j, k - 1),
-                (i, j, k + 1),
-            ] {
-                if !self.cubes.contains(&(a, b, c)) {
+        for cube in &self.cubes {
+            for neighbor in cube.neighbors6() {
+                if !self.cubes.contains(&neighbor) {
                     result += 1;
                 }
             }
         }
 
-        result
+        Ok(result)
     }
 
-    pub fn part2(&self) -> u32 {
+    fn part2(&self) -> Result<u32, Infallible> {
         let mut result = 0;
 
+        let (min, max) = self.bounds();
         let mut trapped = Trapped {
             cubes: &self.cubes,
             already_seen: Default::default(),
-            maximum_dimension: self
-                .cubes
-                .iter()
-                .flat_map(|&(i, j, k)| [i, j, k])
-                .max()
-                .unwrap()
-                + 1,
+            min,
+            max,
         };
 
-        for &(i, j, k) in &self.cubes {
-            for (a, b, c) in [
-                (i - 1, j, k),
-                (i + 1, j, k),
-                (i, j - 1, k),
-                (i, j + 1, k),
-                (i, j, k - 1),
-                (i, j, k + 1),
-            ] {
-                if !self.cubes.contains(&(a, b, c)) && !trapped.is_trapped(a, b, c) {
+        for cube in &self.cubes {
+            for neighbor in cube.neighbors6() {
+                if !self.cubes.contains(&neighbor) && !trapped.is_trapped(neighbor) {
                     result += 1;
                 }
             }
         }
 
-        result
+        Ok(result)
+    }
+}
+
+impl Solution {
+    // the corners of a box with room all the way around the droplet, so that anything that gets
+    // to its edge has gotten outside
+    fn bounds(&self) -> (Point3, Point3) {
+        let (min_x, max_x) = self
+            .cubes
+            .iter()
+            .map(|cube| cube.x)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_y, max_y) = self
+            .cubes
+            .iter()
+            .map(|cube| cube.y)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_z, max_z) = self
+            .cubes
+            .iter()
+            .map(|cube| cube.z)
+            .minmax()
+            .into_option()
+            .unwrap();
+        (
+            Point3::new(min_x - 1, min_y - 1, min_z - 1),
+            Point3::new(max_x + 1, max_y + 1, max_z + 1),
+        )
     }
 }
 
 struct Trapped<'a> {
-    cubes: &'a HashSet<(i32, i32, i32)>,
-    already_seen: HashMap<(i32, i32, i32), bool>,
-    maximum_dimension: i32,
+    cubes: &'a HashSet<Point3>,
+    already_seen: HashMap<Point3, bool>,
+    // the corners of the box to search in, from Solution::bounds
+    min: Point3,
+    max: Point3,
 }
 
 impl<'a> Trapped<'a> {
-    fn is_trapped(&mut self, i: i32, j: i32, k: i32) -> bool {
-        if let Some(&already) = self.already_seen.get(&(i, j, k)) {
+    fn is_trapped(&mut self, start: Point3) -> bool {
+        if let Some(&already) = self.already_seen.get(&start) {
             return already;
         }
 
-        let mut to_visit = VecDeque::from(vec![(i, j, k)]);
-        let mut visited = [(i, j, k)].into_iter().collect::<HashSet<_>>();
-
-        while let Some((i, j, k)) = to_visit.pop_front() {
-            if (i, j, k) == (-1, -1, -1) || self.already_seen.get(&(i, j, k)) == Some(&false) {
-                // we've reached the outside, definitively, or something we already know was on the
-                // outside
-                for (a, b, c) in visited.into_iter() {
-                    if let Some(old) = self.already_seen.insert((a, b, c), false) {
-                        assert!(
-                            !old,
-                            "tried to flip the cached state of {:?} to false",
-                            (a, b, c)
-                        );
-                    }
-                }
-                return false;
+        let (cubes, min, max) = (self.cubes, self.min, self.max);
+        let outside = min;
+        let search = search::bfs_until(
+            start,
+            |point| {
+                point.neighbors6().filter(move |neighbor| {
+                    // we can't move through a cube, and we confine ourselves to the box around the
+                    // droplet, since our input seems to be small integers; even if it's something
+                    // like 100x100x100 then we still only have at most a million iterations
+                    !cubes.contains(neighbor)
+                        && (min.x..=max.x).contains(&neighbor.x)
+                        && (min.y..=max.y).contains(&neighbor.y)
+                        && (min.z..=max.z).contains(&neighbor.z)
+                })
+            },
+            // we've reached the outside, definitively, or something we already know about
+            |point| *point == outside || self.already_seen.contains_key(point),
+        );
+
+        // if we've exhausted all our paths, and we still didn't reach the outside, then we're
+        // trapped; otherwise we're in the same state as whatever we reached
+        let trapped = match search.goal() {
+            None => true,
+            Some(point) => self.already_seen.get(point).copied().unwrap_or(false),
+        };
+
+        for &point in search.distances().keys() {
+            if let Some(old) = self.already_seen.insert(point, trapped) {
+                assert_eq!(
+                    old, trapped,
+                    "tried to flip the cached state of {:?} to {}",
+                    point, trapped
+                );
             }
+        }
+        trapped
+    }
+}
 
-            if self.already_seen.get(&(i, j, k)) == Some(&true) {
-                // we've reached something we definitively know is trapped, so we must be trapped
-                for (a, b, c) in visited.into_iter() {
-                    if let Some(old) = self.already_seen.insert((a, b, c), true) {
-                        assert!(
-                            old,
-                            "tried to flip the cached state of {:?} to true",
-                            (a, b, c)
-                        );
-                    }
-                }
-                return true;
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 20;
+
+    /// A lumpy ball of lava, full of air pockets, that fits in a cube `size` on a side.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let size = size.max(1) as i32;
+        let center = (size - 1) as f64 / 2.0;
+        let radius = size as f64 / 2.0;
+
+        let mut input = String::new();
+        for (x, y, z) in itertools::iproduct!(0..size, 0..size, 0..size) {
+            let distance = [x, y, z]
+                .iter()
+                .map(|&c| (c as f64 - center).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            if distance <= radius && rng.gen_bool(0.8) {
+                input += &format!("{},{},{}\n", x, y, z);
             }
+        }
 
-            for (a, b, c) in [
-                (i - 1, j, k),
-                (i + 1, j, k),
-                (i, j - 1, k),
-                (i, j + 1, k),
-                (i, j, k - 1),
-                (i, j, k + 1),
-            ] {
-                if self.cubes.contains(&(a, b, c)) {
-                    // we can't move through a cube
-                    continue;
-                }
+        // there has to be at least one cube
+        if input.is_empty() {
+            input += "0,0,0\n";
+        }
+        input
+    }
+}
 
-                // confine ourselves to an NxNxN cube, since our input seems to be small integers;
-                // even if it's something like 100x100x100 then we still only have at most a million
-                // iterations
-                if a > self.maximum_dimension
-                    || b > self.maximum_dimension
-                    || c > self.maximum_dimension
-                    || a < -1
-                    || b < -1
-                    || c < -1
-                {
-                    continue;
-                }
+// the slow way: counts the faces that every pair of cubes share, and floods the whole box around
+// the droplet from outside once, instead of searching outward from each face to see if it's
+// trapped
+#[cfg(test)]
+mod reference {
+    use super::*;
 
-                if visited.contains(&(a, b, c)) {
-                    continue;
-                }
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<u32, Infallible> {
+            let touching = self
+                .cubes
+                .iter()
+                .tuple_combinations()
+                .filter(|&(&a, &b)| a.manhattan(b) == 1)
+                .count();
 
-                visited.insert((a, b, c));
-                to_visit.push_back((a, b, c));
-            }
+            Ok((6 * self.cubes.len() - 2 * touching) as u32)
         }
 
-        // if we've exhausted all our paths, and we still didn't reach (0,0,0), then we're trapped.
-        for (a, b, c) in visited.into_iter() {
-            if let Some(old) = self.already_seen.insert((a, b, c), true) {
-                assert!(
-                    old,
-                    "tried to flip the cached state of {:?} to true",
-                    (a, b, c)
-                );
-            }
+        fn reference_part2(&self) -> Result<u32, Infallible> {
+            let coordinates = |cube: &Point3| [cube.x, cube.y, cube.z];
+            let min = (0..3)
+                .map(|i| self.cubes.iter().map(|c| coordinates(c)[i]).min().unwrap() - 1)
+                .collect_vec();
+            let max = (0..3)
+                .map(|i| self.cubes.iter().map(|c| coordinates(c)[i]).max().unwrap() + 1)
+                .collect_vec();
+            let in_box =
+                |point: &Point3| (0..3).all(|i| (min[i]..=max[i]).contains(&coordinates(point)[i]));
+
+            let outside = search::bfs(Point3::new(min[0], min[1], min[2]), |point| {
+                point
+                    .neighbors6()
+                    .filter(|neighbor| in_box(neighbor) && !self.cubes.contains(neighbor))
+                    .collect_vec()
+            });
+
+            Ok(self
+                .cubes
+                .iter()
+                .flat_map(|cube| cube.neighbors6())
+                .filter(|neighbor| outside.distance(neighbor).is_some())
+                .count() as u32)
         }
-        return true;
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(8);
+prelude::parser_tests!(8);
+prelude::differential_tests!(8);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn anywhere() {
+        // all of its faces are on the outside, even with nothing at the origin
+        let solution = Solution::new("-5,0,0\n").unwrap();
+        assert_eq!(solution.part1(), Ok(6));
+        assert_eq!(solution.part2(), Ok(6));
+    }
+
+    #[test]
+    fn unmeasurable() {
+        let error = Solution::new("").err().unwrap();
+        assert_eq!(error.line, 1);
+
+        let error = Solution::new("1,2,3\n1,2147483647,3\n").err().unwrap();
+        assert_eq!((error.line, error.column), (2, 3));
     }
 }
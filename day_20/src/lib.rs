@@ -1,21 +1,73 @@
+use std::convert::Infallible;
+
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i64 = 3;
+pub const EXAMPLE_PART2: i64 = 1623178306;
+
+/// What part 2 multiplies every number by before mixing, and how many times it mixes them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub decryption_key: i64,
+    pub part2_rounds: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            decryption_key: 811589153,
+            part2_rounds: 10,
+        }
+    }
+}
 
+#[derive(Serialize)]
 pub struct Solution {
     input: Vec<i64>,
+    #[serde(skip)]
+    config: Config,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Solution {
+impl Configurable for Solution {
+    type Config = Config;
+
+    fn with_config(input: &str, config: Config) -> Result<Self, ParseError> {
         init();
 
-        let input = input.lines().map(|line| line.parse().unwrap()).collect();
+        let input = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| parse_word(i + 1, line, line, "a number"))
+            .collect::<Result<Vec<_>, _>>()?;
         debug!("parsed: {input:?}");
 
-        Solution { input }
+        // the answers are counted from the 0, and mixing needs something to move around
+        if input.len() < 2 || !input.contains(&0) {
+            return Err(ParseError::end_of_input(
+                input.len() + 1,
+                "at least two numbers, including a 0",
+            ));
+        }
+
+        Ok(Solution { input, config })
     }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = i64;
+    type Part2 = i64;
+    type Error = Infallible;
 
-    pub fn part1(&self) -> i64 {
+    fn new(input: &str) -> Result<Self, ParseError> {
+        Self::with_config(input, Config::default())
+    }
+
+    fn part1(&self) -> Result<i64, Infallible> {
         let arrangement = self.mix(self.input.iter().collect(), 1);
 
         let zero_position = arrangement
@@ -23,18 +75,18 @@ impl Solution {
             .position(|&&element| element == 0)
             .expect("couldn't find zero");
 
-        arrangement[(zero_position + 1000) % arrangement.len()]
+        Ok(arrangement[(zero_position + 1000) % arrangement.len()]
             + arrangement[(zero_position + 2000) % arrangement.len()]
-            + arrangement[(zero_position + 3000) % arrangement.len()]
+            + arrangement[(zero_position + 3000) % arrangement.len()])
     }
 
-    pub fn part2(&self) -> i64 {
+    fn part2(&self) -> Result<i64, Infallible> {
         let mut arrangement = self.input.iter().collect();
 
-        const KEY: i64 = 811589153;
+        let key = self.config.decryption_key;
 
-        for _round in 0..10 {
-            arrangement = self.mix(arrangement, KEY);
+        for _round in 0..self.config.part2_rounds {
+            arrangement = self.mix(arrangement, key);
         }
 
         let zero_position = arrangement
@@ -42,11 +94,14 @@ impl Solution {
             .position(|&&element| element == 0)
             .expect("couldn't find zero");
 
-        KEY * (arrangement[(zero_position + 1000) % arrangement.len()]
-            + arrangement[(zero_position + 2000) % arrangement.len()]
-            + arrangement[(zero_position + 3000) % arrangement.len()])
+        Ok(key
+            * (arrangement[(zero_position + 1000) % arrangement.len()]
+                + arrangement[(zero_position + 2000) % arrangement.len()]
+                + arrangement[(zero_position + 3000) % arrangement.len()]))
     }
+}
 
+impl Solution {
     fn mix<'a>(&'a self, mut arrangement: Vec<&'a i64>, factor: i64) -> Vec<&'a i64> {
         // arrangement is a Vec of references so we can always find *exactly* the one we're looking for.
 
@@ -80,30 +135,93 @@ impl Solution {
     }
 }
 
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 5000;
+
+    /// `size` numbers (at least two), exactly one of them 0.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let size = size.max(2);
+        let zero = rng.gen_range(0..size);
+
+        (0..size)
+            .map(|i| {
+                let number = match i {
+                    _ if i == zero => 0,
+                    _ if rng.gen() => rng.gen_range(1..=10_000),
+                    _ => -rng.gen_range(1..=10_000),
+                };
+                format!("{}\n", number)
+            })
+            .collect()
+    }
+}
+
+// the slow way: moves each number by swapping it with its neighbor, once for every place it moves,
+// all the way around the circle as many times as it takes
 #[cfg(test)]
-mod test {
-    use crate::Solution;
+mod reference {
+    use super::*;
+
+    impl Solution {
+        fn swap_mix(&self, key: i64, rounds: u32) -> i64 {
+            let numbers = self.input.iter().map(|&n| n * key).collect_vec();
+            let len = numbers.len();
+            // which of the numbers is in each place around the circle
+            let mut circle = (0..len).collect_vec();
+
+            for _ in 0..rounds {
+                for (i, &number) in numbers.iter().enumerate() {
+                    let mut position = circle.iter().position(|&j| j == i).unwrap();
+                    for _ in 0..number.abs() {
+                        let next = match number.signum() {
+                            1 => (position + 1) % len,
+                            _ => (position + len - 1) % len,
+                        };
+                        circle.swap(position, next);
+                        position = next;
+                    }
+                }
+            }
+
+            let zero = circle.iter().position(|&j| numbers[j] == 0).unwrap();
+            [1000, 2000, 3000]
+                .iter()
+                .map(|offset| numbers[circle[(zero + offset) % len]])
+                .sum()
+        }
+    }
 
-    #[test]
-    fn example() {
-        let solution = Solution::new(
-            "1
-2
--3
-3
--2
-0
-4",
-        );
-
-        assert_eq!(solution.part1(), 3);
-        assert_eq!(solution.part2(), 1623178306);
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i64, Infallible> {
+            Ok(self.swap_mix(1, 1))
+        }
+
+        fn reference_part2(&self) -> Result<i64, Infallible> {
+            Ok(self.swap_mix(self.config.decryption_key, self.config.part2_rounds))
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(50);
+prelude::parser_tests!(50);
+prelude::differential_tests!(
+    20,
+    Config {
+        decryption_key: 3,
+        part2_rounds: 2,
     }
+);
+
+#[cfg(test)]
+mod test {
+    use crate::Solution;
+    use prelude::Solution as _;
 
     #[test]
     fn real_data() {
-        let solution = Solution::new(include_str!("input.txt"));
-        assert_eq!(solution.part1(), 3346);
-        assert_eq!(solution.part2(), 4265712588168);
+        let solution = Solution::new(include_str!("input.txt")).unwrap();
+        assert_eq!(solution.part1(), Ok(3346));
+        assert_eq!(solution.part2(), Ok(4265712588168));
     }
 }
@@ -1,13 +1,22 @@
+use std::fmt::{self, Display};
+
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::alpha1;
 use nom::IResult;
 use prelude::log::debug;
+use prelude::rand::seq::SliceRandom;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 
 use Monkey::*;
 
-#[derive(Debug, Clone)]
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i64 = 152;
+pub const EXAMPLE_PART2: i64 = 301;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 enum Monkey {
     Literal(i64),
     Add(String, String),
@@ -18,67 +27,108 @@ enum Monkey {
 }
 
 impl Monkey {
-    fn eval(&self, monkeys: &HashMap<String, Monkey>) -> Option<i64> {
+    // the two monkeys this one is waiting on, if it's waiting on any
+    fn waits_on(&self) -> Option<(&str, &str)> {
         match self {
-            Literal(i) => Some(*i),
-            Add(m1, m2) => monkeys[m1].eval(monkeys).and_then(|m1_result| {
-                monkeys[m2]
-                    .eval(monkeys)
-                    .map(|m2_result| m1_result + m2_result)
-            }),
-            Subtract(m1, m2) => monkeys[m1].eval(monkeys).and_then(|m1_result| {
-                monkeys[m2]
-                    .eval(monkeys)
-                    .map(|m2_result| m1_result - m2_result)
-            }),
-            Multiply(m1, m2) => monkeys[m1].eval(monkeys).and_then(|m1_result| {
-                monkeys[m2]
-                    .eval(monkeys)
-                    .map(|m2_result| m1_result * m2_result)
-            }),
-            Divide(m1, m2) => monkeys[m1].eval(monkeys).and_then(|m1_result| {
-                monkeys[m2]
-                    .eval(monkeys)
-                    .map(|m2_result| m1_result / m2_result)
-            }),
-            Unknown => None,
-        }
-    }
-
-    fn invert(&self, target: i64, monkeys: &HashMap<String, Monkey>) -> i64 {
+            Add(l, r) | Subtract(l, r) | Multiply(l, r) | Divide(l, r) => Some((l, r)),
+            Literal(_) | Unknown => None,
+        }
+    }
+
+    // what this monkey yells, or None if it depends on a monkey that doesn't know yet
+    fn eval(&self, monkeys: &HashMap<String, Monkey>) -> Result<Option<i64>, String> {
+        let (left, right) = match self {
+            Literal(i) => return Ok(Some(*i)),
+            Unknown => return Ok(None),
+            Add(l, r) | Subtract(l, r) | Multiply(l, r) | Divide(l, r) => (l, r),
+        };
+        let (Some(a), Some(b)) = (monkeys[left].eval(monkeys)?, monkeys[right].eval(monkeys)?)
+        else {
+            return Ok(None);
+        };
+
+        let result = match self {
+            Add(_, _) => a.checked_add(b),
+            Subtract(_, _) => a.checked_sub(b),
+            Multiply(_, _) => a.checked_mul(b),
+            Divide(_, _) if b == 0 => return Err(format!("{} divides by zero", self)),
+            Divide(_, _) => a.checked_div(b),
+            Literal(_) | Unknown => unreachable!(),
+        };
+        result
+            .map(Some)
+            .ok_or_else(|| format!("{} overflows", self))
+    }
+
+    // what humn has to yell for this monkey to yell `target`
+    fn invert(&self, target: i64, monkeys: &HashMap<String, Monkey>) -> Result<i64, String> {
         let (left, right) = match self {
             Add(l, r) | Subtract(l, r) | Multiply(l, r) | Divide(l, r) => {
                 (&monkeys[l], &monkeys[r])
             }
-            Unknown => return target,
-            Literal(_) => panic!("can't invert a literal!"),
+            Unknown => return Ok(target),
+            Literal(_) => return Err("can't work back through a monkey that yells a number".into()),
         };
 
-        match (self, left.eval(monkeys), right.eval(monkeys)) {
+        let overflow = || format!("working back through {} overflows", self);
+        // the truncating division in the monkeys' math means some targets can't be hit at all
+        let impossible = || format!("{} can never be {}", self, target);
+        match (self, left.eval(monkeys)?, right.eval(monkeys)?) {
             // Add and Multiply are commutative, so their new targets should be equal in either direction
-            (Add(_, _), Some(x), None) => right.invert(target - x, monkeys),
-            (Add(_, _), None, Some(x)) => left.invert(target - x, monkeys),
+            (Add(_, _), Some(x), None) => {
+                right.invert(target.checked_sub(x).ok_or_else(overflow)?, monkeys)
+            }
+            (Add(_, _), None, Some(x)) => {
+                left.invert(target.checked_sub(x).ok_or_else(overflow)?, monkeys)
+            }
             (Multiply(_, _), Some(x), None) => {
-                assert_eq!(target % x, 0);
+                if target.checked_rem(x) != Some(0) {
+                    return Err(impossible());
+                }
                 right.invert(target / x, monkeys)
             }
             (Multiply(_, _), None, Some(x)) => {
-                assert_eq!(target % x, 0);
+                if target.checked_rem(x) != Some(0) {
+                    return Err(impossible());
+                }
                 left.invert(target / x, monkeys)
             }
             // Subtract and Divide are not
-            (Subtract(_, _), Some(x), None) => right.invert(x - target, monkeys),
-            (Subtract(_, _), None, Some(x)) => left.invert(target + x, monkeys),
+            (Subtract(_, _), Some(x), None) => {
+                right.invert(x.checked_sub(target).ok_or_else(overflow)?, monkeys)
+            }
+            (Subtract(_, _), None, Some(x)) => {
+                left.invert(target.checked_add(x).ok_or_else(overflow)?, monkeys)
+            }
             (Divide(_, _), Some(x), None) => {
-                assert_eq!(x % target, 0);
+                if x.checked_rem(target) != Some(0) {
+                    return Err(impossible());
+                }
                 right.invert(x / target, monkeys)
             }
-            (Divide(_, _), None, Some(x)) => left.invert(target * x, monkeys),
+            (Divide(_, _), None, Some(0)) => Err(impossible()),
+            (Divide(_, _), None, Some(x)) => {
+                left.invert(target.checked_mul(x).ok_or_else(overflow)?, monkeys)
+            }
             // we already weeded these out immediately upon entering invert().
             (Literal(_), _, _) | (Unknown, _, _) => unreachable!(),
-            // and if neither/both sides is unknown, that's a problem
-            (_, None, None) => panic!("both sides are unknown!"),
-            (_, Some(_), Some(_)) => panic!("both sides are known!"),
+            // and if neither/both sides is unknown, humn is on both sides, or neither
+            (_, None, None) => Err(format!("both sides of {} wait on humn", self)),
+            (_, Some(_), Some(_)) => Err(format!("neither side of {} waits on humn", self)),
+        }
+    }
+}
+
+// the job, the way it's written after the monkey's name
+impl Display for Monkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal(i) => write!(f, "{}", i),
+            Add(m1, m2) => write!(f, "{} + {}", m1, m2),
+            Subtract(m1, m2) => write!(f, "{} - {}", m1, m2),
+            Multiply(m1, m2) => write!(f, "{} * {}", m1, m2),
+            Divide(m1, m2) => write!(f, "{} / {}", m1, m2),
+            Unknown => write!(f, "?"),
         }
     }
 }
@@ -134,49 +184,491 @@ mod parse_details {
     }
 }
 
+#[derive(Serialize)]
 pub struct Solution {
     monkeys: HashMap<String, Monkey>,
 }
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
+/// Writes the monkeys back out the way the input had them, in order by name.
+impl Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, monkey) in self.monkeys.iter().sorted_by_key(|&(name, _)| name) {
+            writeln!(f, "{}: {}", name, monkey)?;
+        }
+        Ok(())
+    }
+}
+
+impl prelude::Solution for Solution {
+    type Part1 = i64;
+    type Part2 = i64;
+    type Error = String;
+
+    fn new(input: &str) -> Result<Self, ParseError> {
         init();
 
-        let monkeys = input
+        let parsed = input
             .lines()
-            .map(|line| parse_monkey(line).unwrap().1)
-            .collect();
+            .enumerate()
+            .map(|(i, line)| {
+                Ok((
+                    i + 1,
+                    line,
+                    parse_line(i + 1, line, "a monkey", parse_monkey)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
+        // every monkey has to be able to hear the monkeys it's waiting on, all the way from root
+        let names = parsed
+            .iter()
+            .map(|(_, _, (name, _))| name.as_str())
+            .collect::<HashSet<_>>();
+        for (line_number, line, (_, monkey)) in &parsed {
+            let Some((left, right)) = monkey.waits_on() else {
+                continue;
+            };
+            // the job comes after the monkey's own name, as "<left> <op> <right>"
+            let (_, job) = line.split_once(": ").unwrap();
+            for (name, position) in [(left, 0), (right, job.len() - right.len())] {
+                if !names.contains(name) {
+                    return Err(ParseError::at(
+                        *line_number,
+                        line,
+                        &job[position..],
+                        "the name of a monkey",
+                    ));
+                }
+            }
+        }
+        for name in ["root", "humn"] {
+            if !names.contains(name) {
+                return Err(ParseError::end_of_input(
+                    parsed.len() + 1,
+                    format!("a monkey named {}", name),
+                ));
+            }
+        }
+
+        let lines = parsed
+            .iter()
+            .map(|(line_number, line, (name, monkey))| {
+                (name.as_str(), (*line_number, *line, monkey))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // root compares two monkeys in part 2, so it has to be waiting on some
+        let (line_number, line, root) = lines["root"];
+        if root.waits_on().is_none() {
+            let (_, job) = line.split_once(": ").unwrap();
+            return Err(ParseError::at(
+                line_number,
+                line,
+                job,
+                "two monkeys for root to compare",
+            ));
+        }
+
+        // and a monkey that ends up waiting on itself would never yell anything
+        let names = parsed
+            .iter()
+            .map(|(_, _, (name, _))| name.as_str())
+            .collect_vec();
+        if let Some(name) = find_cycle(&names, &lines) {
+            let (line_number, line, _) = lines[name];
+            let (_, job) = line.split_once(": ").unwrap();
+            return Err(ParseError::at(
+                line_number,
+                line,
+                job,
+                "a job that doesn't end up waiting on this monkey itself",
+            ));
+        }
+
+        let monkeys: HashMap<_, _> = parsed.into_iter().map(|(_, _, monkey)| monkey).collect();
         debug!("parsed: {:?}", monkeys);
 
-        Solution { monkeys }
+        Ok(Solution { monkeys })
     }
 
-    pub fn part1(&self) -> i64 {
-        self.monkeys["root"].eval(&self.monkeys).unwrap()
+    fn part1(&self) -> Result<i64, String> {
+        self.monkeys["root"]
+            .eval(&self.monkeys)?
+            .ok_or_else(|| "root doesn't yell anything".to_owned())
     }
 
-    pub fn part2(&self) -> i64 {
-        let (left, right) = match &self.monkeys["root"] {
-            Add(l, r) | Subtract(l, r) | Multiply(l, r) | Divide(l, r) => {
-                (&self.monkeys[l], &self.monkeys[r])
-            }
-            _ => panic!("root monkey doesn't have two sides"),
+    fn part2(&self) -> Result<i64, String> {
+        let Some((left, right)) = self.monkeys["root"].waits_on() else {
+            return Err("root monkey doesn't have two sides".to_owned());
         };
 
         let mut monkeys = self.monkeys.clone();
-        monkeys
-            .insert("humn".to_owned(), Unknown)
-            .expect("humn was not previously in the list?!");
+        monkeys.insert("humn".to_owned(), Unknown);
         let monkeys = monkeys;
+        let (left, right) = (&monkeys[left], &monkeys[right]);
 
-        let (target, unknown) = match (left.eval(&monkeys), right.eval(&monkeys)) {
+        let (target, unknown) = match (left.eval(&monkeys)?, right.eval(&monkeys)?) {
             (Some(x), None) => (x, right),
             (None, Some(x)) => (x, left),
-            (None, None) => panic!("neither side is a known value"),
-            (Some(_), Some(_)) => panic!("both sides are known!"),
+            (None, None) => return Err("both sides of root wait on humn".to_owned()),
+            (Some(_), Some(_)) => return Err("neither side of root waits on humn".to_owned()),
         };
         debug!("will try to make one side equal {target}");
 
         unknown.invert(target, &monkeys)
     }
 }
+
+// some monkey that ends up waiting on itself, by way of the monkeys it's waiting on, if there is
+// one: the first one found looking from each of `names` in turn
+fn find_cycle<'a>(
+    names: &[&'a str],
+    lines: &HashMap<&'a str, (usize, &str, &'a Monkey)>,
+) -> Option<&'a str> {
+    // monkeys that are known not to be part of a cycle, and the ones being waited on by whoever is
+    // being looked at now
+    let mut done = HashSet::new();
+    let mut waiting = HashSet::new();
+    for &start in names {
+        let mut to_visit = vec![(start, false)];
+        while let Some((name, finished)) = to_visit.pop() {
+            if finished {
+                waiting.remove(name);
+                done.insert(name);
+                continue;
+            }
+            if done.contains(name) {
+                continue;
+            }
+            if !waiting.insert(name) {
+                return Some(name);
+            }
+
+            to_visit.push((name, true));
+            let (_, _, monkey) = lines[name];
+            if let Some((left, right)) = monkey.waits_on() {
+                to_visit.push((left, false));
+                to_visit.push((right, false));
+            }
+        }
+    }
+    None
+}
+
+// makes up monkeys for the generator, as lines of input, each with a name nobody else has
+struct Troop<'a, R> {
+    rng: &'a mut R,
+    names: HashSet<String>,
+    lines: Vec<String>,
+}
+
+impl<R: Rng> Troop<'_, R> {
+    fn add(&mut self, name: &str, job: String) {
+        self.lines.push(format!("{}: {}\n", name, job));
+    }
+
+    fn new_name(&mut self) -> String {
+        loop {
+            let name: String = (0..4).map(|_| self.rng.gen_range('a'..='z')).collect();
+            if self.names.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    // "left op right", or the other way around if it doesn't matter
+    fn either_way(&mut self, left: &str, op: char, right: &str) -> String {
+        if self.rng.gen() {
+            format!("{} {} {}", left, op, right)
+        } else {
+            format!("{} {} {}", right, op, left)
+        }
+    }
+
+    // something small that `value` can be divided by evenly, if there is anything
+    fn factor(&mut self, value: i64) -> Option<i64> {
+        let factors = (2..=9)
+            .filter(|&f| value != 0 && value % f == 0)
+            .collect_vec();
+        factors.choose(self.rng).copied()
+    }
+
+    // a monkey that yells `value`, waiting on monkeys at most `depth` deep to work it out; every
+    // division comes out even, so truncating never changes anything
+    fn known(&mut self, value: i64, depth: u32) -> String {
+        let name = self.new_name();
+
+        let job = match self.rng.gen_range(0..4) {
+            _ if value > 0 && (depth == 0 || self.rng.gen_bool(0.3)) => value.to_string(),
+            // there aren't any negative literals in the puzzle's input, so take them away instead
+            _ if depth == 0 => {
+                let right = self.rng.gen_range(1..=20) - value;
+                let (left, right) = (self.known(right + value, 0), self.known(right, 0));
+                format!("{} - {}", left, right)
+            }
+            0 => {
+                let left = self.rng.gen_range(1..=20);
+                let (left, right) = (
+                    self.known(left, depth - 1),
+                    self.known(value - left, depth - 1),
+                );
+                self.either_way(&left, '+', &right)
+            }
+            1 => {
+                let right = self.rng.gen_range(1..=20);
+                let (left, right) = (
+                    self.known(value + right, depth - 1),
+                    self.known(right, depth - 1),
+                );
+                format!("{} - {}", left, right)
+            }
+            2 if self.factor(value).is_some() => {
+                let factor = self.factor(value).unwrap();
+                let (left, right) = (
+                    self.known(factor, depth - 1),
+                    self.known(value / factor, depth - 1),
+                );
+                self.either_way(&left, '*', &right)
+            }
+            _ => {
+                let divisor = self.rng.gen_range(2..=9);
+                let (left, right) = (
+                    self.known(value * divisor, depth - 1),
+                    self.known(divisor, depth - 1),
+                );
+                format!("{} / {}", left, right)
+            }
+        };
+
+        self.add(&name, job);
+        name
+    }
+}
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 70;
+
+    /// `size` monkeys between root and humn, each of them also waiting on a few monkeys that don't
+    /// depend on humn at all.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let answer = rng.gen_range(1..=5000);
+        let yelled = rng.gen_range(1..=5000);
+        let mut troop = Troop {
+            rng,
+            names: ["root", "humn"].map(str::to_owned).into(),
+            lines: vec![],
+        };
+        troop.add("humn", yelled.to_string());
+
+        // work outward from humn, keeping track of what each monkey along the way yells when humn
+        // yells the answer, which is exactly what part 2 has to work back from
+        let (mut unknown, mut value) = ("humn".to_owned(), answer);
+        for _ in 0..size {
+            let depth = troop.rng.gen_range(0..=3);
+            let factor = troop.factor(value);
+
+            let job = match troop.rng.gen_range(0..4) {
+                0 => {
+                    let other = troop.rng.gen_range(1..=1000);
+                    let known = troop.known(other, depth);
+                    value += other;
+                    troop.either_way(&unknown, '+', &known)
+                }
+                1 => {
+                    let other = troop.rng.gen_range(1..=1000);
+                    let known = troop.known(other, depth);
+                    if troop.rng.gen() {
+                        value -= other;
+                        format!("{} - {}", unknown, known)
+                    } else {
+                        value = other - value;
+                        format!("{} - {}", known, unknown)
+                    }
+                }
+                // humn's side never gets divided into anything, since it might come out to 0 with
+                // what humn yells in part 1
+                _ if factor.is_some() && troop.rng.gen() => {
+                    let factor = factor.unwrap();
+                    let known = troop.known(factor, depth);
+                    value /= factor;
+                    format!("{} / {}", unknown, known)
+                }
+                _ if value.abs() < 1_000_000_000 => {
+                    let other = troop.rng.gen_range(2..=9);
+                    let known = troop.known(other, depth);
+                    value *= other;
+                    troop.either_way(&unknown, '*', &known)
+                }
+                _ => {
+                    let other = troop.rng.gen_range(1..=1000);
+                    let known = troop.known(other, depth);
+                    value -= other;
+                    format!("{} - {}", unknown, known)
+                }
+            };
+
+            unknown = troop.new_name();
+            troop.add(&unknown, job);
+        }
+
+        let depth = troop.rng.gen_range(0..=3);
+        let known = troop.known(value, depth);
+        let job = troop.either_way(&unknown, '+', &known);
+        troop.add("root", job);
+
+        let Troop { rng, mut lines, .. } = troop;
+        lines.shuffle(rng);
+        lines.concat()
+    }
+}
+
+// the slow way: works out what every monkey yells, in an order where each one has already heard
+// the monkeys it's waiting on, and in part 2 tries one number after another for humn until root's
+// two sides match, instead of working backward from root
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        // every monkey, each one after the monkeys it's waiting on, found by going around the troop
+        // over and over, picking up whoever's heard everything they need to
+        fn order(&self) -> Vec<&str> {
+            let mut order = vec![];
+            let mut heard = HashSet::new();
+            while order.len() < self.monkeys.len() {
+                for (name, monkey) in &self.monkeys {
+                    let ready = match monkey.waits_on() {
+                        Some((l, r)) => heard.contains(l) && heard.contains(r),
+                        None => true,
+                    };
+                    if ready && heard.insert(name.as_str()) {
+                        order.push(name.as_str());
+                    }
+                }
+            }
+            order
+        }
+
+        // what each monkey yells, with humn yelling `humn` instead if it's given; anything that
+        // overflows counts as not yelling anything, and so does everything that depends on it.  So
+        // does dividing unevenly, when we're trying out numbers for humn: the answer can't be one
+        // that only works because the division got rounded off
+
+        fn yelled<'a>(
+            &self,
+            order: &[&'a str],
+            humn: Option<i64>,
+        ) -> HashMap<&'a str, Option<i64>> {
+            let mut yelled: HashMap<&str, Option<i64>> = HashMap::new();
+            for &name in order {
+                let value = match (name, &self.monkeys[name]) {
+                    ("humn", _) if humn.is_some() => humn,
+                    (_, Literal(i)) => Some(*i),
+                    (_, Add(l, r)) => yelled[l.as_str()]
+                        .zip(yelled[r.as_str()])
+                        .and_then(|(a, b)| a.checked_add(b)),
+                    (_, Subtract(l, r)) => yelled[l.as_str()]
+                        .zip(yelled[r.as_str()])
+                        .and_then(|(a, b)| a.checked_sub(b)),
+                    (_, Multiply(l, r)) => yelled[l.as_str()]
+                        .zip(yelled[r.as_str()])
+                        .and_then(|(a, b)| a.checked_mul(b)),
+                    (_, Divide(l, r)) => {
+                        yelled[l.as_str()]
+                            .zip(yelled[r.as_str()])
+                            .and_then(|(a, b)| match humn {
+                                Some(_) if a.checked_rem(b) != Some(0) => None,
+                                _ => a.checked_div(b),
+                            })
+                    }
+                    (_, Unknown) => None,
+                };
+                yelled.insert(name, value);
+            }
+            yelled
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i64, String> {
+            self.yelled(&self.order(), None)["root"]
+                .ok_or_else(|| "root doesn't yell anything".to_owned())
+        }
+
+        fn reference_part2(&self) -> Result<i64, String> {
+            let Some((left, right)) = self.monkeys["root"].waits_on() else {
+                return Err("root monkey doesn't have two sides".to_owned());
+            };
+
+            let order = self.order();
+            // 0, 1, -1, 2, -2, ... once the second 0 is out of the way
+            Ok((0..)
+                .flat_map(|i: i64| [i, -i])
+                .skip(1)
+                .find(|&humn| {
+                    let yelled = self.yelled(&order, Some(humn));
+                    yelled[left].is_some() && yelled[left] == yelled[right]
+                })
+                .unwrap())
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(20);
+prelude::parser_tests!(20);
+prelude::differential_tests!(10);
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn unsolvable_troops() {
+        for (input, line) in [
+            // nobody for part 2 to stand in for
+            ("root: aaaa + bbbb\naaaa: 1\nbbbb: 2\n", 4),
+            // nothing for root to compare
+            ("root: 5\nhumn: 1\n", 1),
+            // bbbb is waiting on itself, by way of cccc
+            (
+                "root: aaaa + bbbb\naaaa: 2\nbbbb: cccc - humn\ncccc: bbbb + humn\nhumn: 1\n",
+                3,
+            ),
+        ] {
+            let error = Solution::new(input).err().unwrap();
+            assert_eq!(error.line, line, "{}", input);
+        }
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let solution =
+            Solution::new("root: aaaa + bbbb\naaaa: humn / zero\nbbbb: 3\nzero: 0\nhumn: 1\n")
+                .unwrap();
+        assert!(solution.part1().is_err());
+        assert!(solution.part2().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(seed: u64) {
+            let solution = Solution::new(&Solution::generate_seeded(seed, 20)).unwrap();
+            let reparsed = Solution::new(&solution.to_string()).unwrap();
+            prop_assert_eq!(reparsed.monkeys, solution.monkeys);
+        }
+
+        #[test]
+        fn part2_balances_root(seed: u64) {
+            let solution = Solution::new(&Solution::generate_seeded(seed, 20)).unwrap();
+            let answer = solution.part2().unwrap();
+
+            let mut monkeys = solution.monkeys.clone();
+            monkeys.insert("humn".to_owned(), Literal(answer));
+            let (left, right) = monkeys["root"].waits_on().unwrap();
+            prop_assert_eq!(monkeys[left].eval(&monkeys), monkeys[right].eval(&monkeys));
+        }
+    }
+}
@@ -1,155 +1,179 @@
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::VecDeque;
+use std::convert::Infallible;
 
-use itertools::MinMaxResult;
 use prelude::log::debug;
+use prelude::rand::Rng;
 use prelude::*;
+use serde::Serialize;
 
 use Direction::*;
 
+pub const EXAMPLE: &str = include_str!("example.txt");
+pub const EXAMPLE_PART1: i32 = 110;
+pub const EXAMPLE_PART2: u32 = 20;
+
+#[derive(Serialize)]
 pub struct Solution {
-    elves: HashSet<(i32, i32)>,
+    elves: HashSet<Point2>,
 }
 
-enum Direction {
-    North,
-    South,
-    East,
-    West,
+// an elf can move in a direction if there's nobody there, or diagonally on either side of it
+fn can_move(direction: Direction, from: Point2, elves: &SparseGrid<()>) -> bool {
+    let ahead = from + direction.offset();
+    let check = [
+        ahead + direction.turn_left().offset(),
+        ahead,
+        ahead + direction.turn_right().offset(),
+    ];
+
+    !check.iter().any(|&to| elves.contains(to))
 }
 
-impl Direction {
-    fn can_move(&self, from: (i32, i32), elves: &BTreeSet<(i32, i32)>) -> bool {
-        let (from_row, from_col) = from;
-        let check = match self {
-            North => [
-                (from_row - 1, from_col - 1),
-                (from_row - 1, from_col),
-                (from_row - 1, from_col + 1),
-            ],
-            South => [
-                (from_row + 1, from_col - 1),
-                (from_row + 1, from_col),
-                (from_row + 1, from_col + 1),
-            ],
-            West => [
-                (from_row - 1, from_col - 1),
-                (from_row, from_col - 1),
-                (from_row + 1, from_col - 1),
-            ],
-            East => [
-                (from_row - 1, from_col + 1),
-                (from_row, from_col + 1),
-                (from_row + 1, from_col + 1),
-            ],
-        };
+impl prelude::Solution for Solution {
+    type Part1 = i32;
+    type Part2 = u32;
+    type Error = Infallible;
 
-        !check.iter().any(|to| elves.contains(to))
-    }
+    fn new(input: &str) -> Result<Self, ParseError> {
+        init();
 
-    fn move_elf(&self, from: (i32, i32)) -> (i32, i32) {
-        let (from_row, from_col) = from;
-        match self {
-            North => (from_row - 1, from_col),
-            South => (from_row + 1, from_col),
-            West => (from_row, from_col - 1),
-            East => (from_row, from_col + 1),
+        for (i, line) in input.lines().enumerate() {
+            if let Some(position) = line.find(|c| c != '#' && c != '.') {
+                return Err(ParseError::at(i + 1, line, &line[position..], "# or ."));
+            }
         }
-    }
-}
 
-impl Solution {
-    pub fn new(input: &str) -> Self {
-        init();
-
-        let elves = input
+        let elves: HashSet<_> = input
             .lines()
             .enumerate()
             .flat_map(|(row, line)| {
                 line.chars().enumerate().filter_map(move |(column, c)| {
                     if c == '#' {
-                        Some((row as i32, column as i32))
+                        Some(Point2::new(column as i32, row as i32))
                     } else {
                         None
                     }
                 })
             })
             .collect();
+        if elves.is_empty() {
+            return Err(ParseError::end_of_input(
+                input.lines().count() + 1,
+                "at least one elf",
+            ));
+        }
         debug!("parsed: {elves:#?}");
 
-        Self { elves }
+        Ok(Self { elves })
     }
 
-    pub fn part1(&self) -> i32 {
-        let mut elves = self.elves.iter().copied().collect::<BTreeSet<_>>();
-        let mut directions = VecDeque::from(vec![North, South, West, East]);
+    fn part1(&self) -> Result<i32, Infallible> {
+        let mut grove = self.grove();
+        grove.run_for(10);
+        let elves = grove.state();
 
-        for _round in 0..10 {
-            let moves = calculate_moves(&elves, &directions);
+        // there's always at least one elf, but if there weren't, they'd cover no ground at all
+        let Some((top_left, bottom_right)) = elves.bounds() else {
+            return Ok(0);
+        };
+        let size = bottom_right - top_left + Point2::new(1, 1);
 
-            for (to, froms) in moves {
-                if froms.len() == 1 {
-                    assert!(elves.remove(&froms[0]));
-                    assert!(elves.insert(to));
-                }
-            }
+        Ok(size.x * size.y - elves.len() as i32)
+    }
 
-            debug!("after {_round}: {elves:#?}");
+    fn part2(&self) -> Result<u32, Infallible> {
+        let mut grove = self.grove();
+        grove.run();
 
-            let first = directions.pop_front().unwrap();
-            directions.push_back(first);
+        Ok(grove.rounds())
+    }
+}
+
+impl Solution {
+    /// The elves where they started, before the first round.
+    pub fn grove(&self) -> Grove {
+        Grove {
+            elves: self.elves.iter().map(|&elf| (elf, ())).collect(),
+            directions: VecDeque::from(vec![North, South, West, East]),
+            rounds: 0,
+            settled: false,
         }
+    }
+}
 
-        let MinMaxResult::MinMax(min_row, max_row) = elves.iter().map(|(row, _)| row).minmax() else {
-            panic!("ran out of elves?");
-        };
-        let MinMaxResult::MinMax(min_col, max_col) = elves.iter().map(|(_, col)| col).minmax() else {
-            unreachable!();
-        };
+/// The elves spreading out, one round per step, until a round where none of them move.
+pub struct Grove {
+    elves: SparseGrid<()>,
+    // the order the elves consider moving in this round
+    directions: VecDeque<Direction>,
+    rounds: u32,
+    settled: bool,
+}
 
-        (max_row - min_row + 1) * (max_col - min_col + 1) - elves.len() as i32
+impl Grove {
+    /// How many rounds have gone by, including the one where nobody moved, if it's happened.
+    pub fn rounds(&self) -> u32 {
+        self.rounds
     }
+}
 
-    pub fn part2(&self) -> u32 {
-        let mut elves = self.elves.iter().copied().collect::<BTreeSet<_>>();
-        let mut directions = VecDeque::from(vec![North, South, West, East]);
+impl Simulation for Grove {
+    type State = SparseGrid<()>;
 
-        for round in 1.. {
-            let moves = calculate_moves(&elves, &directions);
+    fn step(&mut self) {
+        if self.settled {
+            return;
+        }
 
-            if !moves.iter().any(|(_to, froms)| froms.len() == 1) {
-                return round;
-            }
+        let moves = calculate_moves(&self.elves, &self.directions);
+        self.rounds += 1;
 
-            for (to, froms) in moves {
-                if froms.len() == 1 {
-                    assert!(elves.remove(&froms[0]));
-                    assert!(elves.insert(to));
-                }
-            }
+        if !moves.iter().any(|(_to, froms)| froms.len() == 1) {
+            self.settled = true;
+            return;
+        }
 
-            let first = directions.pop_front().unwrap();
-            directions.push_back(first);
+        for (to, froms) in moves {
+            if froms.len() == 1 {
+                assert!(self.elves.remove(froms[0]).is_some());
+                assert!(self.elves.insert(to, ()).is_none());
+            }
         }
 
-        panic!("Did not find any stable state!");
+        debug!(
+            "after {}:\n{}",
+            self.rounds,
+            self.elves.render('.', |_| '#')
+        );
+
+        let first = self.directions.pop_front().unwrap();
+        self.directions.push_back(first);
+    }
+
+    fn state(&self) -> &SparseGrid<()> {
+        &self.elves
+    }
+
+    fn is_done(&self) -> bool {
+        self.settled
     }
 }
 
 fn calculate_moves(
-    elves: &BTreeSet<(i32, i32)>,
+    elves: &SparseGrid<()>,
     directions: &VecDeque<Direction>,
-) -> HashMap<(i32, i32), Vec<(i32, i32)>> {
-    let mut moves = HashMap::<(i32, i32), Vec<(i32, i32)>>::new();
+) -> HashMap<Point2, Vec<Point2>> {
+    let mut moves = HashMap::<Point2, Vec<Point2>>::new();
 
-    for &from in elves {
+    for (from, _) in elves.iter() {
         // if we can move in all four directions, then there aren't any elves adjacent and we shouldn't move
-        if directions.iter().all(|d| d.can_move(from, &elves)) {
+        if directions.iter().all(|&d| can_move(d, from, elves)) {
             continue;
         }
 
-        let to = directions.iter().find_map(|d| {
-            if d.can_move(from, &elves) {
-                Some(d.move_elf(from))
+        let to = directions.iter().find_map(|&d| {
+            if can_move(d, from, elves) {
+                Some(from + d.offset())
             } else {
                 None
             }
@@ -162,3 +186,123 @@ fn calculate_moves(
 
     moves
 }
+
+impl Generate for Solution {
+    const PUZZLE_SIZE: usize = 70;
+
+    /// A square `size` on a side, about half full of elves, and at least one of them.
+    fn generate(rng: &mut impl Rng, size: usize) -> String {
+        let size = size.max(1);
+        let mut grid = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.gen() { '#' } else { '.' })
+                    .collect::<Vec<_>>()
+            })
+            .collect_vec();
+        grid[rng.gen_range(0..size)][rng.gen_range(0..size)] = '#';
+
+        grid.into_iter()
+            .map(|row| row.into_iter().chain(['\n']).collect::<String>())
+            .collect()
+    }
+}
+
+// the slow way: keeps the elves in a plain list, looks through all of them for each neighbor, and
+// counts up how many elves proposed each square by going back through every proposal
+#[cfg(test)]
+mod reference {
+    use super::*;
+
+    impl Solution {
+        // the elves after each round, until one where none of them moved
+        fn rounds(&self) -> Vec<Vec<Point2>> {
+            let order = [North, South, West, East];
+            let mut elves = self.elves.iter().copied().collect_vec();
+            let mut rounds = vec![];
+
+            for round in 0.. {
+                let neighbors = |elf: Point2| {
+                    (-1..=1)
+                        .cartesian_product(-1..=1)
+                        .map(|(dx, dy)| Point2::new(dx, dy))
+                        .filter(|&d| d != Point2::default() && elves.contains(&(elf + d)))
+                        .collect_vec()
+                };
+                let proposals = elves
+                    .iter()
+                    .map(|&elf| {
+                        let neighbors = neighbors(elf);
+                        if neighbors.is_empty() {
+                            return None;
+                        }
+                        (0..4)
+                            .map(|i| order[(round + i) % 4])
+                            .find(|direction| {
+                                // the three squares on that side are a step further along it
+                                let o = direction.offset();
+                                !neighbors.iter().any(|d| d.x * o.x + d.y * o.y == 1)
+                            })
+                            .map(|direction| elf + direction.offset())
+                    })
+                    .collect_vec();
+
+                let mut moved = false;
+                let next = elves
+                    .iter()
+                    .zip(&proposals)
+                    .map(|(&elf, proposal)| match proposal {
+                        Some(to) if proposals.iter().filter(|&p| p == proposal).count() == 1 => {
+                            moved = true;
+                            *to
+                        }
+                        _ => elf,
+                    })
+                    .collect_vec();
+
+                elves = next;
+                rounds.push(elves.clone());
+                if !moved {
+                    break;
+                }
+            }
+
+            rounds
+        }
+    }
+
+    impl Reference for Solution {
+        fn reference_part1(&self) -> Result<i32, Infallible> {
+            let rounds = self.rounds();
+            let elves = rounds.get(9).unwrap_or_else(|| rounds.last().unwrap());
+            let width = elves.iter().map(|elf| elf.x).max().unwrap()
+                - elves.iter().map(|elf| elf.x).min().unwrap()
+                + 1;
+            let height = elves.iter().map(|elf| elf.y).max().unwrap()
+                - elves.iter().map(|elf| elf.y).min().unwrap()
+                + 1;
+            Ok(width * height - elves.len() as i32)
+        }
+
+        fn reference_part2(&self) -> Result<u32, Infallible> {
+            Ok(self.rounds().len() as u32)
+        }
+    }
+}
+
+prelude::example_tests!();
+prelude::generator_tests!(10);
+prelude::parser_tests!(10);
+prelude::differential_tests!(10);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn one_elf() {
+        let solution = Solution::new("#\n").unwrap();
+        assert_eq!(solution.part1(), Ok(0));
+        assert_eq!(solution.part2(), Ok(1));
+    }
+}
@@ -0,0 +1,24 @@
+use crate::*;
+
+/// Looks for a marker in a datastream that arrives a chunk at a time, like one read from a
+/// `ReadableStream`, without holding on to any more of it than the marker's window.
+#[wasm_bindgen]
+pub struct Day06MarkerFinder(::day_06::MarkerFinder);
+
+#[wasm_bindgen]
+impl Day06MarkerFinder {
+    /// For a marker of `window` characters that are all different.
+    pub fn new(window: usize) -> Day06MarkerFinder {
+        Day06MarkerFinder(::day_06::MarkerFinder::new(window))
+    }
+
+    /// Takes in the next chunk, and returns how many characters had been received by the end of
+    /// the marker, once it's been found.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Option<usize>, JsValue> {
+        catch_panics(|| Ok(self.0.feed(chunk)))
+    }
+
+    pub fn marker(&self) -> Option<usize> {
+        self.0.marker()
+    }
+}
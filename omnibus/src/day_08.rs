@@ -0,0 +1,22 @@
+use js_sys::{Array, Function};
+
+use crate::*;
+
+#[wasm_bindgen]
+impl Day08 {
+    /// Calls `tree(row, column, visible, scenicScore)` for every tree in the forest, row by row.
+    pub fn draw(&self, tree: Function) -> Result<(), JsValue> {
+        catch_panics(|| {
+            for ((row, column), surveyed) in self.0.survey().iter() {
+                let args = Array::of4(
+                    &JsValue::from(row),
+                    &JsValue::from(column),
+                    &JsValue::from(surveyed.is_visible()),
+                    &JsValue::from(surveyed.scenic_score()),
+                );
+                tree.apply(&JsValue::NULL, &args)?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use js_sys::Function;
+use web_sys::{Document, HtmlDivElement};
+
+use crate::*;
+
+#[wasm_bindgen]
+pub struct Day14Renderer(::day_14::Renderer);
+
+#[wasm_bindgen]
+impl Day14 {
+    pub fn make_renderer(
+        &self,
+        document: Document,
+        target: &HtmlDivElement,
+    ) -> Result<Day14Renderer, JsValue> {
+        catch_panics(|| Ok(Day14Renderer(self.0.render(document, target)?)))
+    }
+}
+
+#[wasm_bindgen]
+impl Day14Renderer {
+    pub fn tick_part1(&mut self, grains: u32, callback: &Function) -> Result<(), JsValue> {
+        catch_panics(|| self.0.tick_part1(grains, callback))
+    }
+
+    pub fn tick_part2(&mut self, grains: u32, callback: &Function) -> Result<(), JsValue> {
+        catch_panics(|| self.0.tick_part2(grains, callback))
+    }
+}
@@ -1,41 +1,141 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use js_sys::Reflect;
+use prelude::{Configurable, Export, Generate, ParseError, Solution};
 use wasm_bindgen::prelude::*;
 
+fn to_js_error<E: std::fmt::Display>(error: E) -> JsValue {
+    js_sys::Error::new(&error.to_string()).into()
+}
+
+fn config_from_json<S: Configurable>(config: &str) -> Result<S::Config, JsValue> {
+    serde_json::from_str(config).map_err(|e| to_js_error(format!("invalid config: {}", e)))
+}
+
+// an Error whose message is the whole description, but which also carries each piece of it so the
+// page can point at the spot in the input
+fn parse_error_to_js(error: ParseError) -> JsValue {
+    let js_error = to_js_error(&error);
+    for (key, value) in [
+        ("line", JsValue::from(error.line)),
+        ("column", JsValue::from(error.column)),
+        ("expected", JsValue::from(error.expected)),
+        ("found", JsValue::from(error.found)),
+    ] {
+        let _ = Reflect::set(&js_error, &JsValue::from_str(key), &value);
+    }
+    js_error
+}
+
+/// Runs `f`, turning a panic inside it into an Error thrown to JS.  Every export that calls into a
+/// day crate goes through this.
+///
+/// This only works when panics unwind, which they don't in a wasm build on stable, where
+/// `panic=abort` is all there is: a panic still traps and takes the module down with it, though the
+/// hook from `prelude::init` will at least have logged why.  Day crates are meant to return errors
+/// instead (`ParseError` from `new`, and their own `Error` from the parts), but nothing makes sure
+/// of that, so the site calls into them from a worker it can throw out and replace after a trap.
+/// This is only a backstop for natively, or for once wasm can unwind.
+fn catch_panics<T, F>(f: F) -> Result<T, JsValue>
+where
+    F: FnOnce() -> Result<T, JsValue>,
+{
+    // nothing is shared across calls into a day crate, so there's nothing to see half-updated
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.as_str()
+        } else {
+            "no message"
+        };
+        Err(to_js_error(format!("panicked: {}", message)))
+    })
+}
+
 macro_rules! common_day {
-    ($krate: ident, $strukt: ident, $part1_result: ty, $part2_result: ty) => {
+    ($krate: ident, $strukt: ident) => {
         #[wasm_bindgen]
-        pub struct $strukt($krate::Solution);
+        pub struct $strukt(::$krate::Solution);
 
         #[wasm_bindgen]
         impl $strukt {
-            pub fn new(input: &str) -> Self {
-                Self($krate::Solution::new(input))
+            pub fn example() -> String {
+                ::$krate::EXAMPLE.to_owned()
+            }
+
+            pub fn new(input: &str) -> Result<$strukt, JsValue> {
+                catch_panics(|| {
+                    Ok(Self(
+                        <::$krate::Solution as Solution>::new(input).map_err(parse_error_to_js)?,
+                    ))
+                })
             }
 
-            pub fn part1(&self) -> $part1_result {
-                self.0.part1()
+            pub fn part1(&self) -> Result<JsValue, JsValue> {
+                catch_panics(|| self.0.part1().map(JsValue::from).map_err(to_js_error))
+            }
+
+            pub fn part2(&self) -> Result<JsValue, JsValue> {
+                catch_panics(|| self.0.part2().map(JsValue::from).map_err(to_js_error))
+            }
+
+            /// What the input was parsed into, as JSON.
+            pub fn export(&self) -> Result<String, JsValue> {
+                catch_panics(|| Ok(self.0.export()))
+            }
+        }
+    };
+    // for days with puzzle constants that can be changed: `config` is JSON, and anything left out
+    // of it keeps the puzzle's value
+    ($krate: ident, $strukt: ident, configurable) => {
+        common_day!($krate, $strukt);
+
+        #[wasm_bindgen]
+        impl $strukt {
+            pub fn default_config() -> String {
+                serde_json::to_string(&<::$krate::Config>::default()).unwrap()
             }
 
-            pub fn part2(&self) -> $part2_result {
-                self.0.part2()
+            pub fn with_config(input: &str, config: &str) -> Result<$strukt, JsValue> {
+                catch_panics(|| {
+                    let config = config_from_json::<::$krate::Solution>(config)?;
+                    Ok(Self(
+                        <::$krate::Solution as Configurable>::with_config(input, config)
+                            .map_err(parse_error_to_js)?,
+                    ))
+                })
             }
         }
     };
 }
 
-common_day!(day_01, Day01, u64, u64);
-common_day!(day_02, Day02, i64, i64);
-common_day!(day_03, Day03, u64, u64);
-common_day!(day_07, Day07, Result<u64, JsValue>, Result<u64, JsValue>);
-common_day!(day_11, Day11, u32, u64);
-common_day!(day_12, Day12, u32, u32);
-common_day!(day_13, Day13, usize, usize);
-common_day!(day_16, Day16, i32, i32);
-common_day!(day_17, Day17, u32, u64);
-common_day!(day_18, Day18, u32, u32);
-common_day!(day_20, Day20, i64, i64);
-common_day!(day_21, Day21, i64, i64);
-common_day!(day_23, Day23, i32, u32);
+common_day!(day_01, Day01);
+common_day!(day_02, Day02);
+common_day!(day_03, Day03);
+common_day!(day_04, Day04);
+common_day!(day_05, Day05);
+common_day!(day_06, Day06, configurable);
+common_day!(day_07, Day07, configurable);
+common_day!(day_08, Day08);
+common_day!(day_09, Day09, configurable);
+common_day!(day_10, Day10);
+common_day!(day_11, Day11, configurable);
+common_day!(day_12, Day12);
+common_day!(day_13, Day13);
+common_day!(day_14, Day14, configurable);
+common_day!(day_15, Day15, configurable);
+common_day!(day_16, Day16, configurable);
+common_day!(day_17, Day17, configurable);
+common_day!(day_18, Day18);
+common_day!(day_20, Day20, configurable);
+common_day!(day_21, Day21);
+common_day!(day_23, Day23);
 
 pub mod day_05;
-pub mod day_09;
+pub mod day_06;
+pub mod day_08;
 pub mod day_10;
+pub mod day_14;
+pub mod logging;
+pub mod registry;
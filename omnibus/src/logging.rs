@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use js_sys::{Array, Object};
+use prelude::log::LevelFilter;
+use prelude::logging::{capture, CapturedRecord};
+
+use crate::*;
+
+static CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Changes the most detailed level of log message that gets to the console (or captured): one of
+/// "off", "error", "warn", "info", "debug" or "trace".
+#[wasm_bindgen]
+pub fn set_log_level(level: &str) -> Result<(), JsValue> {
+    let level = level
+        .parse::<LevelFilter>()
+        .map_err(|_| to_js_error(format!("{:?} is not a log level", level)))?;
+    prelude::init();
+    prelude::logging::set_level(level);
+
+    Ok(())
+}
+
+/// While enabled, everything logged during `solve()` or `solve_instrumented()` comes back in a
+/// `logs` array of `{level, target, message}` on its result, or on the error it throws.
+#[wasm_bindgen]
+pub fn set_log_capture(enabled: bool) {
+    CAPTURE.store(enabled, Ordering::Relaxed);
+}
+
+fn records_to_js(records: Vec<CapturedRecord>) -> Result<Array, JsValue> {
+    records
+        .into_iter()
+        .map(|record| {
+            let object = Object::new();
+            for (key, value) in [
+                ("level", JsValue::from(record.level.as_str())),
+                ("target", JsValue::from(record.target)),
+                ("message", JsValue::from(record.message)),
+            ] {
+                Reflect::set(&object, &JsValue::from_str(key), &value)?;
+            }
+            Ok(object)
+        })
+        .collect()
+}
+
+// runs f, and if log capture is on, attaches what it logged to whatever it returns
+pub(crate) fn with_captured_logs<F>(f: F) -> Result<Object, JsValue>
+where
+    F: FnOnce() -> Result<Object, JsValue>,
+{
+    if !CAPTURE.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let (result, records) = capture(f);
+    let logs = records_to_js(records)?;
+    let target: &JsValue = match &result {
+        Ok(object) => object,
+        Err(error) => error,
+    };
+    Reflect::set(target, &JsValue::from_str("logs"), &logs)?;
+
+    result
+}
@@ -0,0 +1,411 @@
+use std::marker::PhantomData;
+
+use js_sys::{Array, Function, Object};
+use prelude::instrument::{measure, Measurement};
+use prelude::progress::{with_progress, CancellationToken, Progress};
+
+use crate::logging::with_captured_logs;
+use crate::*;
+
+/// An answer that can be handed to JS, along with what `typeof` will say about it there.
+pub trait Answer: Into<JsValue> {
+    const JS_TYPE: &'static str;
+}
+
+macro_rules! answers {
+    ($js_type: literal: $($t: ty),*) => {
+        $(
+            impl Answer for $t {
+                const JS_TYPE: &'static str = $js_type;
+            }
+        )*
+    };
+}
+
+answers!("number": i32, u32, usize);
+answers!("bigint": i64, u64);
+answers!("string": String);
+
+struct Day {
+    number: u32,
+    title: &'static str,
+    class_name: &'static str,
+    part1_type: &'static str,
+    part2_type: &'static str,
+    has_renderer: bool,
+    example: &'static str,
+    // the puzzle's constants, as JSON, for the days that can change them
+    default_config: Option<String>,
+    // and the example's, which are the same unless the example is a smaller version of the puzzle
+    example_config: Option<String>,
+    solve: fn(&str, Option<&str>) -> Result<Solved, JsValue>,
+    export: fn(&str, Option<&str>) -> Result<String, JsValue>,
+    puzzle_size: usize,
+    generate: fn(u64, usize) -> String,
+}
+
+struct Solved {
+    part1: JsValue,
+    part2: JsValue,
+    parse_measurement: Measurement,
+    part1_measurement: Measurement,
+    part2_measurement: Measurement,
+}
+
+impl Day {
+    fn new<S>(
+        number: u32,
+        title: &'static str,
+        class_name: &'static str,
+        has_renderer: bool,
+        example: &'static str,
+    ) -> Self
+    where
+        S: Export + Generate,
+        S::Part1: Answer,
+        S::Part2: Answer,
+    {
+        Day {
+            number,
+            title,
+            class_name,
+            part1_type: S::Part1::JS_TYPE,
+            part2_type: S::Part2::JS_TYPE,
+            has_renderer,
+            example,
+            default_config: None,
+            example_config: None,
+            solve: solve_with::<Plain<S>>,
+            export: export_with::<Plain<S>>,
+            puzzle_size: S::PUZZLE_SIZE,
+            generate: S::generate_seeded,
+        }
+    }
+
+    fn configurable<S>(self) -> Self
+    where
+        S: Configurable + Export,
+        S::Part1: Answer,
+        S::Part2: Answer,
+    {
+        let config = serde_json::to_string(&S::Config::default()).unwrap();
+        Day {
+            default_config: Some(config.clone()),
+            example_config: Some(config),
+            solve: solve_with::<Configured<S>>,
+            export: export_with::<Configured<S>>,
+            ..self
+        }
+    }
+
+    // for the days whose example needs different constants than the puzzle's own, after
+    // `configurable`
+    fn example_config<S: Configurable>(self, config: S::Config) -> Self {
+        Day {
+            example_config: Some(serde_json::to_string(&config).unwrap()),
+            ..self
+        }
+    }
+}
+
+// how a day's Solution gets made out of the input, and the config's JSON if it was given one
+trait Parse {
+    type Solution: Export;
+
+    fn parse(input: &str, config: Option<&str>) -> Result<Self::Solution, JsValue>;
+}
+
+struct Plain<S>(PhantomData<S>);
+
+impl<S: Export> Parse for Plain<S> {
+    type Solution = S;
+
+    fn parse(input: &str, config: Option<&str>) -> Result<S, JsValue> {
+        if config.is_some() {
+            return Err(to_js_error("this day doesn't take a config"));
+        }
+        S::new(input).map_err(parse_error_to_js)
+    }
+}
+
+struct Configured<S>(PhantomData<S>);
+
+impl<S: Configurable + Export> Parse for Configured<S> {
+    type Solution = S;
+
+    fn parse(input: &str, config: Option<&str>) -> Result<S, JsValue> {
+        let config = config.map(config_from_json::<S>).transpose()?;
+        S::with_config(input, config.unwrap_or_default()).map_err(parse_error_to_js)
+    }
+}
+
+fn solve_with<P>(input: &str, config: Option<&str>) -> Result<Solved, JsValue>
+where
+    P: Parse,
+    <P::Solution as Solution>::Part1: Answer,
+    <P::Solution as Solution>::Part2: Answer,
+{
+    let (solution, parse_measurement) = measure(|| P::parse(input, config));
+    let solution = solution?;
+    let (part1, part1_measurement) = measure(|| solution.part1());
+    let part1 = part1.map_err(to_js_error)?;
+    let (part2, part2_measurement) = measure(|| solution.part2());
+    let part2 = part2.map_err(to_js_error)?;
+
+    Ok(Solved {
+        part1: part1.into(),
+        part2: part2.into(),
+        parse_measurement,
+        part1_measurement,
+        part2_measurement,
+    })
+}
+
+fn export_with<P: Parse>(input: &str, config: Option<&str>) -> Result<String, JsValue> {
+    Ok(P::parse(input, config)?.export())
+}
+
+fn days() -> Vec<Day> {
+    vec![
+        Day::new::<::day_01::Solution>(1, "Calorie Counting", "Day01", false, ::day_01::EXAMPLE),
+        Day::new::<::day_02::Solution>(2, "Rock Paper Scissors", "Day02", false, ::day_02::EXAMPLE),
+        Day::new::<::day_03::Solution>(
+            3,
+            "Rucksack Reorganization",
+            "Day03",
+            false,
+            ::day_03::EXAMPLE,
+        ),
+        Day::new::<::day_04::Solution>(4, "Camp Cleanup", "Day04", false, ::day_04::EXAMPLE),
+        Day::new::<::day_05::Solution>(5, "Supply Stacks", "Day05", true, ::day_05::EXAMPLE),
+        Day::new::<::day_06::Solution>(6, "Tuning Trouble", "Day06", false, ::day_06::EXAMPLE)
+            .configurable::<::day_06::Solution>(),
+        Day::new::<::day_07::Solution>(
+            7,
+            "No Space Left On Device",
+            "Day07",
+            false,
+            ::day_07::EXAMPLE,
+        )
+        .configurable::<::day_07::Solution>(),
+        Day::new::<::day_08::Solution>(8, "Treetop Tree House", "Day08", true, ::day_08::EXAMPLE),
+        Day::new::<::day_09::Solution>(9, "Rope Bridge", "Day09", false, ::day_09::EXAMPLE)
+            .configurable::<::day_09::Solution>(),
+        Day::new::<::day_10::Solution>(10, "Cathode-Ray Tube", "Day10", true, ::day_10::EXAMPLE),
+        Day::new::<::day_11::Solution>(
+            11,
+            "Monkey in the Middle",
+            "Day11",
+            false,
+            ::day_11::EXAMPLE,
+        )
+        .configurable::<::day_11::Solution>(),
+        Day::new::<::day_12::Solution>(
+            12,
+            "Hill Climbing Algorithm",
+            "Day12",
+            false,
+            ::day_12::EXAMPLE,
+        ),
+        Day::new::<::day_13::Solution>(13, "Distress Signal", "Day13", false, ::day_13::EXAMPLE),
+        Day::new::<::day_14::Solution>(14, "Regolith Reservoir", "Day14", true, ::day_14::EXAMPLE)
+            .configurable::<::day_14::Solution>(),
+        Day::new::<::day_15::Solution>(
+            15,
+            "Beacon Exclusion Zone",
+            "Day15",
+            false,
+            ::day_15::EXAMPLE,
+        )
+        .configurable::<::day_15::Solution>()
+        .example_config::<::day_15::Solution>(::day_15::Config::example()),
+        Day::new::<::day_16::Solution>(
+            16,
+            "Proboscidea Volcanium",
+            "Day16",
+            false,
+            ::day_16::EXAMPLE,
+        )
+        .configurable::<::day_16::Solution>(),
+        Day::new::<::day_17::Solution>(17, "Pyroclastic Flow", "Day17", false, ::day_17::EXAMPLE)
+            .configurable::<::day_17::Solution>(),
+        Day::new::<::day_18::Solution>(18, "Boiling Boulders", "Day18", false, ::day_18::EXAMPLE),
+        Day::new::<::day_20::Solution>(
+            20,
+            "Grove Positioning System",
+            "Day20",
+            false,
+            ::day_20::EXAMPLE,
+        )
+        .configurable::<::day_20::Solution>(),
+        Day::new::<::day_21::Solution>(21, "Monkey Math", "Day21", false, ::day_21::EXAMPLE),
+        Day::new::<::day_23::Solution>(23, "Unstable Diffusion", "Day23", false, ::day_23::EXAMPLE),
+    ]
+}
+
+fn object(fields: &[(&str, JsValue)]) -> Result<Object, JsValue> {
+    let object = Object::new();
+    for (key, value) in fields {
+        Reflect::set(&object, &JsValue::from_str(key), value)?;
+    }
+    Ok(object)
+}
+
+/// Describes every day that can be solved: `{day, title, className, part1Type, part2Type,
+/// hasRenderer, example, config, exampleConfig, puzzleSize}`, where the answer types are what
+/// `typeof` will say about them, `config` is JSON of the puzzle's constants, for the days where
+/// they can be changed, `exampleConfig` is the same for the example, and `puzzleSize` is about how
+/// big the puzzle's own inputs are, as [`generate`] counts it.
+#[wasm_bindgen]
+pub fn list_days() -> Result<Array, JsValue> {
+    days()
+        .into_iter()
+        .map(|day| {
+            object(&[
+                ("day", day.number.into()),
+                ("title", day.title.into()),
+                ("className", day.class_name.into()),
+                ("part1Type", day.part1_type.into()),
+                ("part2Type", day.part2_type.into()),
+                ("hasRenderer", day.has_renderer.into()),
+                ("example", day.example.into()),
+                (
+                    "config",
+                    day.default_config.map_or(JsValue::UNDEFINED, JsValue::from),
+                ),
+                (
+                    "exampleConfig",
+                    day.example_config.map_or(JsValue::UNDEFINED, JsValue::from),
+                ),
+                ("puzzleSize", day.puzzle_size.into()),
+            ])
+        })
+        .collect()
+}
+
+fn find_day(day: u32) -> Result<Day, JsValue> {
+    days()
+        .into_iter()
+        .find(|entry| entry.number == day)
+        .ok_or_else(|| to_js_error(format!("day {} hasn't been solved", day)))
+}
+
+// {milliseconds, counters: {name: count, ...}}
+fn measurement_to_js(measurement: Measurement) -> Result<JsValue, JsValue> {
+    let counters = Object::new();
+    for (name, count) in measurement.counters {
+        Reflect::set(
+            &counters,
+            &JsValue::from_str(name),
+            &JsValue::from(count as f64),
+        )?;
+    }
+
+    Ok(object(&[
+        (
+            "milliseconds",
+            (measurement.elapsed.as_secs_f64() * 1000.0).into(),
+        ),
+        ("counters", counters.into()),
+    ])?
+    .into())
+}
+
+// runs the day, calling on_progress(done, total) whenever it reports how far it's gotten; total is
+// undefined if the day doesn't know, and on_progress returning false cancels the rest of the run
+fn run(
+    day: u32,
+    input: &str,
+    on_progress: Option<Function>,
+    config: Option<String>,
+) -> Result<Solved, JsValue> {
+    let solve = find_day(day)?.solve;
+    let config = config.as_deref();
+    let Some(on_progress) = on_progress else {
+        return catch_panics(|| solve(input, config));
+    };
+
+    let token = CancellationToken::new();
+    let on_progress = {
+        let token = token.clone();
+        move |progress: Progress| {
+            let total = progress
+                .total
+                .map_or(JsValue::UNDEFINED, |total| (total as f64).into());
+            let keep_going =
+                on_progress.call2(&JsValue::NULL, &(progress.done as f64).into(), &total);
+            if keep_going.map_or(true, |keep_going| keep_going == JsValue::FALSE) {
+                token.cancel();
+            }
+        }
+    };
+
+    catch_panics(|| with_progress(&token, on_progress, || solve(input, config)))
+}
+
+/// Solves both parts of `day` for `input`, as `{day, part1, part2}`.
+///
+/// If given, `on_progress(done, total)` is called as the longer-running parts go along (`total` is
+/// undefined when there's no telling), and can return `false` to cancel them.  `config` is JSON
+/// like the `config` from [`list_days`], with whichever of the puzzle's constants should be
+/// different.
+#[wasm_bindgen]
+pub fn solve(
+    day: u32,
+    input: &str,
+    on_progress: Option<Function>,
+    config: Option<String>,
+) -> Result<Object, JsValue> {
+    with_captured_logs(|| {
+        let solved = run(day, input, on_progress, config)?;
+        object(&[
+            ("day", day.into()),
+            ("part1", solved.part1),
+            ("part2", solved.part2),
+        ])
+    })
+}
+
+/// Like [`solve`], but also says how long parsing and each part took, and what each of them counted
+/// along the way: `{day, part1, part2, timings: {parse, part1, part2}}`, where each timing is
+/// `{milliseconds, counters: {name: count, ...}}`.
+#[wasm_bindgen]
+pub fn solve_instrumented(
+    day: u32,
+    input: &str,
+    on_progress: Option<Function>,
+    config: Option<String>,
+) -> Result<Object, JsValue> {
+    with_captured_logs(|| {
+        let solved = run(day, input, on_progress, config)?;
+        let timings = object(&[
+            ("parse", measurement_to_js(solved.parse_measurement)?),
+            ("part1", measurement_to_js(solved.part1_measurement)?),
+            ("part2", measurement_to_js(solved.part2_measurement)?),
+        ])?;
+
+        object(&[
+            ("day", day.into()),
+            ("part1", solved.part1),
+            ("part2", solved.part2),
+            ("timings", timings.into()),
+        ])
+    })
+}
+
+/// What `day` parsed `input` into (with `config`, as in [`solve`]), as JSON: see what the parser
+/// understood, or hand it to some other tool.
+#[wasm_bindgen]
+pub fn export(day: u32, input: &str, config: Option<String>) -> Result<String, JsValue> {
+    let export = find_day(day)?.export;
+    catch_panics(|| export(input, config.as_deref()))
+}
+
+/// A made-up input for `day`, the same every time for the same `seed`, and `size` big (in whatever
+/// that day counts: lines, monkeys, valves, ...), or about as big as the puzzle's own inputs.
+#[wasm_bindgen]
+pub fn generate(day: u32, seed: u32, size: Option<usize>) -> Result<String, JsValue> {
+    let day = find_day(day)?;
+    let size = size.unwrap_or(day.puzzle_size);
+    catch_panics(|| Ok((day.generate)(seed.into(), size)))
+}
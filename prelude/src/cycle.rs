@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Where a simulation starts repeating itself: the state after `start + length` steps has the same
+/// fingerprint as the state after `start` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: u64,
+    pub length: u64,
+}
+
+/// The values a simulation took on for each step until it either started repeating itself or ran
+/// out of steps.
+#[derive(Debug, Clone)]
+pub struct History {
+    values: Vec<i64>,
+    cycle: Option<Cycle>,
+}
+
+impl History {
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// The value after `steps` steps, extrapolated from the cycle if the simulation didn't run that
+    /// far, assuming that each time around the cycle adds the same amount to the value.  `None` if
+    /// it didn't run that far and there's no cycle, or if the value is too big for an i64.
+    pub fn value_after(&self, steps: u64) -> Option<i64> {
+        if let Some(&value) = usize::try_from(steps)
+            .ok()
+            .and_then(|steps| self.values.get(steps))
+        {
+            return Some(value);
+        }
+
+        let Cycle { start, length } = self.cycle?;
+        let per_cycle =
+            self.values[(start + length) as usize].checked_sub(self.values[start as usize])?;
+        let cycles = (steps - start) / length;
+        let remainder = (steps - start) % length;
+
+        i64::try_from(cycles)
+            .ok()?
+            .checked_mul(per_cycle)?
+            .checked_add(self.values[(start + remainder) as usize])
+    }
+}
+
+/// Steps `state` until the `fingerprint` of a state repeats, or `limit` steps have gone by,
+/// recording its `value` after each one.
+///
+/// Whatever `fingerprint` returns has to capture everything that decides what happens in later
+/// steps, so that two states with the same fingerprint will go on to do the same thing.  It can
+/// return `None` for states that aren't worth comparing yet, like before a simulation has settled
+/// down.
+pub fn run<S, K, E>(
+    mut state: S,
+    limit: u64,
+    mut step: impl FnMut(&mut S) -> Result<(), E>,
+    mut fingerprint: impl FnMut(&S) -> Option<K>,
+    mut value: impl FnMut(&S) -> i64,
+) -> Result<History, E>
+where
+    K: Eq + Hash,
+{
+    let mut values = vec![value(&state)];
+    let mut seen = HashMap::new();
+
+    for steps in 0..=limit {
+        if let Some(key) = fingerprint(&state) {
+            if let Some(&start) = seen.get(&key) {
+                log::info!("found a cycle from step {start} to step {steps}");
+                return Ok(History {
+                    values,
+                    cycle: Some(Cycle {
+                        start,
+                        length: steps - start,
+                    }),
+                });
+            }
+            seen.insert(key, steps);
+        }
+
+        if steps == limit {
+            break;
+        }
+        step(&mut state)?;
+        values.push(value(&state));
+    }
+
+    Ok(History {
+        values,
+        cycle: None,
+    })
+}
+
+/// The `value` of `state` after `steps` steps, which can be far more than could ever be simulated
+/// as long as it starts repeating itself, or `None` if it's too big for an i64.  See [`run`] for
+/// what `fingerprint` has to do.
+pub fn value_after<S, K, E>(
+    state: S,
+    steps: u64,
+    step: impl FnMut(&mut S) -> Result<(), E>,
+    fingerprint: impl FnMut(&S) -> Option<K>,
+    value: impl FnMut(&S) -> i64,
+) -> Result<Option<i64>, E>
+where
+    K: Eq + Hash,
+{
+    let history = run(state, steps, step, fingerprint, value)?;
+    // we either got all the way there, or found a cycle to get us the rest of the way
+    Ok(history.value_after(steps))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[test]
+    fn extrapolates() {
+        // counting up by 1 through 0, 1, ..., 6, then back around to 2 over and over again: the
+        // total goes up by 2+3+4+5+6 = 20 each time around
+        let step = |(n, total): &mut (u64, i64)| {
+            *n = if *n == 6 { 2 } else { *n + 1 };
+            *total += *n as i64;
+            Ok::<_, Infallible>(())
+        };
+        let fingerprint = |&(n, _): &(u64, i64)| Some(n);
+        let total = |&(_, total): &(u64, i64)| total;
+
+        let history = run((0, 0), 100, step, fingerprint, total).unwrap();
+        assert_eq!(
+            history.cycle(),
+            Some(Cycle {
+                start: 2,
+                length: 5
+            })
+        );
+        assert_eq!(history.value_after(6), Some(21));
+
+        for steps in [0, 3, 7, 12, 13, 1_000] {
+            let slow = run((0, 0), steps, step, |_| None::<()>, total).unwrap();
+            assert_eq!(slow.cycle(), None);
+            assert_eq!(
+                value_after((0, 0), steps, step, fingerprint, total),
+                Ok(slow.value_after(steps)),
+                "after {steps} steps"
+            );
+        }
+    }
+
+    #[test]
+    fn far_off() {
+        // 1, 2, 1, 2, ...: the total goes up by 3 every two steps
+        let step = |(n, total): &mut (u64, i64)| {
+            *n = 3 - *n;
+            *total += *n as i64;
+            Ok::<_, Infallible>(())
+        };
+        let fingerprint = |&(n, _): &(u64, i64)| Some(n);
+        let total = |&(_, total): &(u64, i64)| total;
+
+        // further than a u32, or a usize on wasm, can count
+        let steps = 1 << 40;
+        assert_eq!(
+            value_after((2, 0), steps, step, fingerprint, total),
+            Ok(Some(3 << 39))
+        );
+        // and further than an i64 can total up
+        assert_eq!(
+            value_after((2, 0), u64::MAX - 1, step, fingerprint, total),
+            Ok(None)
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::ops::{Index, IndexMut};
+
+use serde::Serialize;
+
+use crate::{ParseError, Point2};
+
+const NEIGHBORS4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const NEIGHBORS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A rectangular grid with a `T` in every cell, indexed by `(row, column)` from the top-left.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>, // row by row
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid with `fill` everywhere.
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parses a map with one character per cell, which `cell` turns into a `T` (or `None` if it
+    /// isn't one of the `expected` characters).  Every line has to be as wide as the first.
+    pub fn parse(
+        input: &str,
+        expected: &str,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            let mut line_width = 0;
+            for (offset, c) in line.char_indices() {
+                if Some(line_width) == width {
+                    return Err(ParseError::at(i + 1, line, &line[offset..], "end of line"));
+                }
+                cells.push(
+                    cell(c)
+                        .ok_or_else(|| ParseError::at(i + 1, line, &line[offset..], expected))?,
+                );
+                line_width += 1;
+            }
+
+            if line_width == 0 || width.is_some_and(|width| line_width < width) {
+                return Err(ParseError::end_of_line(i + 1, line, expected));
+            }
+            width = Some(line_width);
+            height += 1;
+        }
+
+        let Some(width) = width else {
+            return Err(ParseError::end_of_input(1, expected));
+        };
+
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (row, column): (usize, usize)) -> Option<&T> {
+        if row < self.height && column < self.width {
+            Some(&self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (row, column): (usize, usize)) -> Option<&mut T> {
+        if row < self.height && column < self.width {
+            Some(&mut self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..][..self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks() won't take a zero
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        assert!(column < self.width, "column {} is out of bounds", column);
+        self.cells[column..].iter().step_by(self.width)
+    }
+
+    /// Every cell, along with where it is, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i / width, i % width), cell))
+    }
+
+    /// Where the first cell (row by row) that satisfies `predicate` is.
+    pub fn position(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<(usize, usize)> {
+        self.iter()
+            .find_map(|(position, cell)| predicate(cell).then_some(position))
+    }
+
+    /// The up-to-four cells that share an edge with `position`.
+    pub fn neighbors4(
+        &self,
+        position: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(position, &NEIGHBORS4)
+    }
+
+    /// The up-to-eight cells that share an edge or a corner with `position`.
+    pub fn neighbors8(
+        &self,
+        position: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(position, &NEIGHBORS8)
+    }
+
+    fn offsets(
+        &self,
+        (row, column): (usize, usize),
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |&(d_row, d_column)| {
+            let neighbor = (
+                row.checked_add_signed(d_row)?,
+                column.checked_add_signed(d_column)?,
+            );
+            self.get(neighbor).map(|_| neighbor)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Draws the grid one character per cell, one line per row.
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        let mut result = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            result.extend(row.iter().map(&mut f));
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: (usize, usize)) -> &T {
+        self.get(position)
+            .unwrap_or_else(|| panic!("{:?} is out of bounds", position))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, position: (usize, usize)) -> &mut T {
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("{:?} is out of bounds", position))
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A grid that only has a `T` where one's been put, and can extend in any direction.  Positions are
+/// [`Point2`]s, with `y` going down the rows like it does in [`Grid`], so it renders the same way
+/// up.  [`Point2::neighbors4`] and [`Point2::neighbors8`] find the cells around one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point2, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whatever was there before.
+    pub fn insert(&mut self, position: Point2, value: T) -> Option<T> {
+        self.cells.insert(position, value)
+    }
+
+    pub fn remove(&mut self, position: Point2) -> Option<T> {
+        self.cells.remove(&position)
+    }
+
+    pub fn get(&self, position: Point2) -> Option<&T> {
+        self.cells.get(&position)
+    }
+
+    pub fn get_mut(&mut self, position: Point2) -> Option<&mut T> {
+        self.cells.get_mut(&position)
+    }
+
+    pub fn contains(&self, position: Point2) -> bool {
+        self.cells.contains_key(&position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Every filled cell, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2, &T)> {
+        self.cells.iter().map(|(&position, cell)| (position, cell))
+    }
+
+    /// The top-left and bottom-right corners (inclusive) of the smallest rectangle around every
+    /// filled cell, if there are any.
+    pub fn bounds(&self) -> Option<(Point2, Point2)> {
+        let mut positions = self.cells.keys();
+        let &first = positions.next()?;
+        Some(
+            positions.fold((first, first), |(top_left, bottom_right), &position| {
+                (
+                    Point2::new(top_left.x.min(position.x), top_left.y.min(position.y)),
+                    Point2::new(
+                        bottom_right.x.max(position.x),
+                        bottom_right.y.max(position.y),
+                    ),
+                )
+            }),
+        )
+    }
+
+    /// Draws everything within [`bounds`](Self::bounds), one character per cell, with `empty`
+    /// wherever nothing's been put.
+    pub fn render(&self, empty: char, mut f: impl FnMut(&T) -> char) -> String {
+        let Some((top_left, bottom_right)) = self.bounds() else {
+            return String::new();
+        };
+
+        let mut result = String::new();
+        for y in top_left.y..=bottom_right.y {
+            result.extend((top_left.x..=bottom_right.x).map(
+                |x| match self.get(Point2::new(x, y)) {
+                    Some(cell) => f(cell),
+                    None => empty,
+                },
+            ));
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl<T> FromIterator<(Point2, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point2, T)>>(iter: I) -> Self {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAP: &str = "ab#\n.cd\n";
+
+    #[test]
+    fn parse_and_index() {
+        let grid = Grid::parse(MAP, "a cell", Some).unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(0, 2)], '#');
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.row(1), &['.', 'c', 'd']);
+        assert_eq!(grid.column(1).collect::<String>(), "bc");
+        assert_eq!(grid.position(|&c| c == 'c'), Some((1, 1)));
+        assert_eq!(grid.to_string(), MAP);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let only_letters = |c: char| c.is_ascii_lowercase().then_some(c);
+        let error = Grid::parse("ab\nc#\n", "a letter", only_letters).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+
+        let error = Grid::parse("abc\nab\n", "a letter", only_letters).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.found.as_str()),
+            (2, 3, "end of line")
+        );
+
+        let error = Grid::parse("ab\nabc\n", "a letter", only_letters).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.expected.as_str()),
+            (2, 3, "end of line")
+        );
+
+        assert!(Grid::parse("", "a letter", only_letters).is_err());
+    }
+
+    #[test]
+    fn neighbors_stay_in_bounds() {
+        let grid = Grid::new(3, 2, 0);
+
+        assert_eq!(
+            grid.neighbors4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbors4((1, 1)).count(), 3);
+        assert_eq!(
+            grid.neighbors8((0, 2)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (1, 2)]
+        );
+        assert_eq!(grid.neighbors8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn sparse() {
+        let mut grid = [(Point2::new(2, -1), 'x'), (Point2::new(0, 1), 'y')]
+            .into_iter()
+            .collect::<SparseGrid<_>>();
+
+        assert_eq!(grid.bounds(), Some((Point2::new(0, -1), Point2::new(2, 1))));
+        assert_eq!(grid.render('.', |&c| c), "..x\n...\ny..\n");
+
+        assert_eq!(grid.remove(Point2::new(2, -1)), Some('x'));
+        assert_eq!(grid.bounds(), Some((Point2::new(0, 1), Point2::new(0, 1))));
+    }
+}
@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How long something took, and whatever was [`count`]ed while it ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Measurement {
+    pub elapsed: Duration,
+    pub counters: BTreeMap<&'static str, u64>,
+}
+
+thread_local! {
+    // None unless something is being measured, so that counting is nearly free otherwise
+    static COUNTERS: RefCell<Option<BTreeMap<&'static str, u64>>> = const { RefCell::new(None) };
+}
+
+/// Adds `by` to the counter called `name`, if this is running inside [`measure`].
+pub fn count(name: &'static str, by: u64) {
+    COUNTERS.with(|counters| {
+        if let Some(counters) = counters.borrow_mut().as_mut() {
+            *counters.entry(name).or_default() += by;
+        }
+    })
+}
+
+/// Runs `f`, keeping track of how long it took and what it counted.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let outer = COUNTERS.with(|counters| counters.replace(Some(BTreeMap::new())));
+    let restore = RestoreCounters(outer);
+    let stopwatch = Stopwatch::start();
+
+    let result = f();
+
+    let elapsed = stopwatch.elapsed();
+    let counters = COUNTERS
+        .with(|counters| counters.take())
+        .unwrap_or_default();
+    drop(restore);
+
+    (result, Measurement { elapsed, counters })
+}
+
+// puts back whatever was being counted outside, even if `f` panics
+struct RestoreCounters(Option<BTreeMap<&'static str, u64>>);
+
+impl Drop for RestoreCounters {
+    fn drop(&mut self) {
+        COUNTERS.with(|counters| counters.replace(self.0.take()));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Stopwatch(std::time::Instant);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Stopwatch {
+    fn start() -> Self {
+        Stopwatch(std::time::Instant::now())
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+// std::time::Instant panics in the browser, so ask JS for milliseconds instead
+#[cfg(target_arch = "wasm32")]
+struct Stopwatch(f64);
+
+#[cfg(target_arch = "wasm32")]
+impl Stopwatch {
+    fn now() -> f64 {
+        // performance.now() is much finer-grained, but there's no window in a worker
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map(|performance| performance.now())
+            .unwrap_or_else(js_sys::Date::now)
+    }
+
+    fn start() -> Self {
+        Stopwatch(Self::now())
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((Self::now() - self.0).max(0.0) / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_only_while_measuring() {
+        count("ignored", 1);
+
+        let ((), outer) = measure(|| {
+            count("rocks", 2);
+            let ((), inner) = measure(|| count("rocks", 5));
+            assert_eq!(inner.counters, BTreeMap::from([("rocks", 5)]));
+            count("rocks", 1);
+        });
+
+        assert_eq!(outer.counters, BTreeMap::from([("rocks", 3)]));
+    }
+
+    #[test]
+    fn stops_counting_after_a_panic() {
+        let result = std::panic::catch_unwind(|| measure(|| panic!("gave up partway")));
+
+        assert!(result.is_err());
+        assert!(COUNTERS.with(|counters| counters.borrow().is_none()));
+    }
+}
@@ -1,12 +1,311 @@
+use std::fmt::Display;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
 pub use prelude_2021::*;
 
 pub extern crate log;
+pub extern crate rand;
+pub extern crate serde;
+
+pub mod cycle;
+pub mod grid;
+pub mod instrument;
+pub mod logging;
+pub mod parse;
+pub mod point;
+pub mod progress;
+pub mod search;
+pub mod simulation;
 
+pub use grid::{Grid, SparseGrid};
+pub use parse::{parse_line, parse_word, ParseError};
+pub use point::{Direction, Point2, Point3};
+pub use progress::Cancelled;
+pub use simulation::Simulation;
+
+// every day_* crate defines its own `struct Solution`, which shadows the trait's name when it comes
+// in through `use prelude::*`; this keeps the trait's methods callable on it anyway.
+pub use crate::Solution as _;
+
+/// Sets up logging (and, in the browser, panic messages) the first time it's called.
+///
+/// In the browser, everything goes to the console.  Natively, it goes to stderr, filtered by
+/// `RUST_LOG` the way `env_logger` does it (e.g. `RUST_LOG=day_16=debug`), and only warnings get
+/// through by default.  Either way, [`logging::set_level`] can change that later.
 pub fn init() {
+    #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
-    #[cfg(debug_assertions)]
-    let _ = console_log::init_with_level(log::Level::Debug);
 
-    #[cfg(not(debug_assertions))]
-    let _ = console_log::init();
+    logging::install();
+}
+
+/// The common shape of every day's puzzle: parse the input once, then answer both parts from the
+/// parsed form.
+pub trait Solution: Sized {
+    type Part1: Display;
+    type Part2: Display;
+    /// Why a part couldn't be answered, even though the input parsed.
+    type Error: Display;
+
+    fn new(input: &str) -> Result<Self, ParseError>;
+
+    fn part1(&self) -> Result<Self::Part1, Self::Error>;
+
+    fn part2(&self) -> Result<Self::Part2, Self::Error>;
+}
+
+/// A [`Solution`] whose puzzle constants (how many rounds, where to start, ...) can be changed from
+/// what the puzzle says, to try out "what if" variations, or to run a smaller version of it.
+pub trait Configurable: Solution {
+    /// The constants, where the default is what the puzzle says.  Fields left out when it's
+    /// deserialized take their defaults, too.
+    type Config: Default + Clone + std::fmt::Debug + Serialize + DeserializeOwned;
+
+    /// Like [`Solution::new`], which is this with the default config.
+    fn with_config(input: &str, config: Self::Config) -> Result<Self, ParseError>;
+}
+
+/// A [`Solution`] that can show what it made of its input, to check what the parser understood or
+/// to hand it to some other tool.  Every day's `Solution` serializes as the model it parsed its
+/// input into, leaving out any config.
+pub trait Export: Solution + Serialize {
+    /// The parsed model, as pretty-printed JSON.
+    fn export(&self) -> String {
+        // nothing a day parses into has map keys that aren't strings, which is all that could fail
+        serde_json::to_string_pretty(self).expect("couldn't serialize the parsed input")
+    }
+}
+
+impl<S: Solution + Serialize> Export for S {}
+
+/// A [`Solution`] that can make up puzzle inputs of its own, to stress-test and benchmark it on more
+/// than the one input each of us got.
+pub trait Generate: Solution {
+    /// About how big the puzzle's own inputs are, counted the way [`Generate::generate`] counts.
+    const PUZZLE_SIZE: usize;
+
+    /// A random input that parses, and that both parts can answer with the default config.  What
+    /// `size` counts (lines, monkeys, valves, ...) is up to each day.
+    fn generate(rng: &mut impl Rng, size: usize) -> String;
+
+    /// Like [`Generate::generate`], but the same input every time for the same `seed`.
+    fn generate_seeded(seed: u64, size: usize) -> String {
+        Self::generate(&mut StdRng::seed_from_u64(seed), size)
+    }
+}
+
+/// A [`Solution`] with a second, slow way of answering each part that takes none of the shortcuts
+/// the real one does (cycle detection, modular arithmetic, memoisation, ...), so that the two can
+/// be checked against each other on small inputs.
+pub trait Reference: Solution {
+    fn reference_part1(&self) -> Result<Self::Part1, Self::Error>;
+
+    fn reference_part2(&self) -> Result<Self::Part2, Self::Error>;
+}
+
+/// Generates a test that the crate's `Solution` gets `EXAMPLE_PART1` and `EXAMPLE_PART2` out of
+/// `EXAMPLE`.  Given a `config`, the example is parsed with it, for the days whose example is a
+/// smaller version of the puzzle than the defaults are for.
+#[macro_export]
+macro_rules! example_tests {
+    () => {
+        $crate::example_tests!(@parse Solution::new);
+    };
+    ($config: expr) => {
+        $crate::example_tests!(@parse |input| {
+            <Solution as $crate::Configurable>::with_config(input, $config)
+        });
+    };
+    (@parse $parse: expr) => {
+        #[cfg(test)]
+        mod example_tests {
+            use $crate::Solution as _;
+
+            // the crate's own Solution, and whatever the config was written in terms of
+            use super::*;
+
+            #[test]
+            fn example() {
+                let solution = ($parse)(EXAMPLE)
+                    .unwrap_or_else(|e| panic!("couldn't parse the example: {}", e));
+
+                assert_eq!(
+                    solution.part1().unwrap_or_else(|e| panic!("part 1: {}", e)),
+                    EXAMPLE_PART1
+                );
+                assert_eq!(
+                    solution.part2().unwrap_or_else(|e| panic!("part 2: {}", e)),
+                    EXAMPLE_PART2
+                );
+            }
+        }
+    };
+}
+
+/// Generates a test that inputs from the crate's [`Generate`] implementation, at `size`, parse and
+/// can be solved, for a handful of seeds.
+#[macro_export]
+macro_rules! generator_tests {
+    ($size: expr) => {
+        #[cfg(test)]
+        mod generator_tests {
+            use $crate::{Generate as _, Solution as _};
+
+            #[test]
+            fn generated() {
+                for seed in 0..10 {
+                    let input = super::Solution::generate_seeded(seed, $size);
+                    let solution = super::Solution::new(&input).unwrap_or_else(|e| {
+                        panic!("couldn't parse seed {}'s input: {}\n{}", seed, e, input)
+                    });
+
+                    if let Err(e) = solution.part1() {
+                        panic!("part 1 of seed {}: {}\n{}", seed, e, input);
+                    }
+                    if let Err(e) = solution.part2() {
+                        panic!("part 2 of seed {}: {}\n{}", seed, e, input);
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Generates property tests that the crate's `Solution::new` returns an error, rather than
+/// panicking, when it's given inputs from its [`Generate`] implementation (at `size`) with a few
+/// characters deleted, copied around, or made up, or when it's given any string at all.  The crate
+/// needs `proptest` in its dev-dependencies.
+#[macro_export]
+macro_rules! parser_tests {
+    ($size: expr) => {
+        #[cfg(test)]
+        mod parser_tests {
+            use ::proptest::prelude::*;
+            use ::proptest::sample::Index;
+            use $crate::{Generate as _, Solution as _};
+
+            // what to do to a generated input, at some position in it
+            #[derive(Debug, Clone)]
+            enum Edit {
+                Delete,
+                // copies the character at another position, so it's something the parser might
+                // be expecting, just not there
+                Copy(Index),
+                Insert(char),
+                Truncate,
+            }
+
+            fn corrupted() -> impl Strategy<Value = String> {
+                let edit = prop_oneof![
+                    Just(Edit::Delete),
+                    any::<Index>().prop_map(Edit::Copy),
+                    any::<char>().prop_map(Edit::Insert),
+                    Just(Edit::Truncate),
+                ];
+                (
+                    any::<u64>(),
+                    prop::collection::vec((any::<Index>(), edit), 1..4),
+                )
+                    .prop_map(|(seed, edits)| {
+                        let input = super::Solution::generate_seeded(seed, $size);
+                        let mut input = input.chars().collect::<Vec<_>>();
+                        for (position, edit) in edits {
+                            if input.is_empty() {
+                                break;
+                            }
+                            let position = position.index(input.len());
+                            match edit {
+                                Edit::Delete => {
+                                    input.remove(position);
+                                }
+                                Edit::Copy(from) => {
+                                    let c = input[from.index(input.len())];
+                                    input.insert(position, c);
+                                }
+                                Edit::Insert(c) => input.insert(position, c),
+                                Edit::Truncate => input.truncate(position),
+                            }
+                        }
+                        input.into_iter().collect()
+                    })
+            }
+
+            proptest! {
+                #[test]
+                fn corrupted_input_doesnt_panic(input in corrupted()) {
+                    let _ = super::Solution::new(&input);
+                }
+
+                #[test]
+                fn any_input_doesnt_panic(input in ".*") {
+                    let _ = super::Solution::new(&input);
+                }
+            }
+        }
+    };
+}
+
+/// Generates a test that inputs from the crate's [`Generate`] implementation, at `size`, get the
+/// same answers from its [`Reference`] implementation as from its `Solution`, for a few dozen
+/// seeds.  Given a `config` too, the inputs are parsed with it, for the days whose reference can
+/// only get through a smaller version of the puzzle.
+#[macro_export]
+macro_rules! differential_tests {
+    ($size: expr) => {
+        $crate::differential_tests!(@parse $size, Solution::new);
+    };
+    ($size: expr, $config: expr) => {
+        $crate::differential_tests!(@parse $size, |input| {
+            <Solution as $crate::Configurable>::with_config(input, $config)
+        });
+    };
+    (@parse $size: expr, $parse: expr) => {
+        #[cfg(test)]
+        mod differential_tests {
+            use std::fmt::Display;
+
+            use $crate::{Generate as _, Reference as _, Solution as _};
+
+            // the crate's own Solution, and whatever the config was written in terms of
+            use super::*;
+
+            fn answer<T, E: Display>(
+                result: Result<T, E>,
+                what: &str,
+                seed: u64,
+                input: &str,
+            ) -> T {
+                result.unwrap_or_else(|e| panic!("{} of seed {}: {}\n{}", what, seed, e, input))
+            }
+
+            #[test]
+            fn matches_reference() {
+                for seed in 0..30 {
+                    let input = Solution::generate_seeded(seed, $size);
+                    let solution = ($parse)(&input).unwrap_or_else(|e| {
+                        panic!("couldn't parse seed {}'s input: {}\n{}", seed, e, input)
+                    });
+
+                    assert_eq!(
+                        answer(solution.part1(), "part 1", seed, &input),
+                        answer(solution.reference_part1(), "reference part 1", seed, &input),
+                        "part 1 of seed {}\n{}",
+                        seed,
+                        input
+                    );
+                    assert_eq!(
+                        answer(solution.part2(), "part 2", seed, &input),
+                        answer(solution.reference_part2(), "reference part 2", seed, &input),
+                        "part 2 of seed {}\n{}",
+                        seed,
+                        input
+                    );
+                }
+            }
+        }
+    };
 }
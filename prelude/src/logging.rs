@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::sync::Once;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// A log record that was kept by [`capture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+thread_local! {
+    // None unless something is being captured
+    static CAPTURED: RefCell<Option<Vec<CapturedRecord>>> = const { RefCell::new(None) };
+}
+
+// sends everything on to the console (or stderr), and keeps a copy of it while capturing
+struct Logger {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        CAPTURED.with(|captured| {
+            if let Some(captured) = captured.borrow_mut().as_mut() {
+                captured.push(CapturedRecord {
+                    level: record.level(),
+                    target: record.target().to_owned(),
+                    message: record.args().to_string(),
+                });
+            }
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        console_log::log(record);
+
+        // env_logger still applies RUST_LOG's filters to what makes it to stderr
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.flush();
+    }
+}
+
+pub(crate) fn install() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        #[cfg(target_arch = "wasm32")]
+        let (logger, level) = (
+            Logger {},
+            if cfg!(debug_assertions) {
+                LevelFilter::Debug
+            } else {
+                LevelFilter::Info
+            },
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let (logger, level) = {
+            // is_test so that `cargo test` captures each test's logs along with the rest of its
+            // output
+            let inner =
+                env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+                    .is_test(true)
+                    .build();
+            let level = inner.filter();
+            (Logger { inner }, level)
+        };
+
+        if log::set_boxed_logger(Box::new(logger)).is_ok() {
+            log::set_max_level(level);
+        }
+    });
+}
+
+/// Changes the most detailed level that gets logged (or captured) from here on, in place of the
+/// default from [`init`](crate::init).
+pub fn set_level(level: LevelFilter) {
+    install();
+    log::set_max_level(level);
+}
+
+/// Runs `f`, keeping a copy of everything it logged.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<CapturedRecord>) {
+    install();
+
+    let outer = CAPTURED.with(|captured| captured.replace(Some(vec![])));
+    let restore = RestoreCaptured(outer);
+
+    let result = f();
+
+    let records = CAPTURED
+        .with(|captured| captured.take())
+        .unwrap_or_default();
+    drop(restore);
+
+    (result, records)
+}
+
+// puts back whatever was capturing outside, even if `f` panics
+struct RestoreCaptured(Option<Vec<CapturedRecord>>);
+
+impl Drop for RestoreCaptured {
+    fn drop(&mut self) {
+        CAPTURED.with(|captured| captured.replace(self.0.take()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn captures_only_inside() {
+        log::warn!("not captured");
+        let ((), records) = capture(|| log::warn!("captured {}", 1));
+
+        assert_eq!(
+            records,
+            vec![CapturedRecord {
+                level: Level::Warn,
+                target: module_path!().to_owned(),
+                message: "captured 1".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn stops_capturing_after_a_panic() {
+        let result = std::panic::catch_unwind(|| capture(|| panic!("gave up partway")));
+
+        assert!(result.is_err());
+        assert!(CAPTURED.with(|captured| captured.borrow().is_none()));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Why a day's input couldn't be parsed, and where.
+///
+/// `line` and `column` are both 1-based, like an editor would show them; `column` counts bytes,
+/// which is the same thing for the plain-ASCII puzzle inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl ParseError {
+    /// An error pointing at `found`, which must be a slice of `line` (for instance, whatever nom
+    /// failed to consume).  Only the first word of it is reported as what was found.
+    pub fn at(line_number: usize, line: &str, found: &str, expected: impl Into<String>) -> Self {
+        let start = line.as_ptr() as usize;
+        let offset = (found.as_ptr() as usize).wrapping_sub(start);
+        debug_assert!(
+            offset <= line.len(),
+            "{:?} is not part of {:?}",
+            found,
+            line
+        );
+        let column = if offset <= line.len() { offset + 1 } else { 1 };
+
+        ParseError {
+            line: line_number,
+            column,
+            expected: expected.into(),
+            found: match found.split_whitespace().next() {
+                Some(word) => format!("{:?}", word),
+                None => "end of line".to_owned(),
+            },
+        }
+    }
+
+    /// An error for a line that stopped before something else was expected.
+    pub fn end_of_line(line_number: usize, line: &str, expected: impl Into<String>) -> Self {
+        ParseError::at(line_number, line, &line[line.len()..], expected)
+    }
+
+    /// An error for input that stopped before something else was expected.  `line_number` is the
+    /// line that would have been next.
+    pub fn end_of_input(line_number: usize, expected: impl Into<String>) -> Self {
+        ParseError {
+            line: line_number,
+            column: 1,
+            expected: expected.into(),
+            found: "end of input".to_owned(),
+        }
+    }
+
+    /// An error for a config that no input could be solved with, rather than anything wrong with
+    /// the input itself, so it's reported at the very start of it.
+    pub fn config(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        ParseError {
+            line: 1,
+            column: 1,
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `word`, a slice of `line`, with [`FromStr`].
+pub fn parse_word<T: FromStr>(
+    line_number: usize,
+    line: &str,
+    word: &str,
+    expected: &str,
+) -> Result<T, ParseError> {
+    word.parse()
+        .map_err(|_| ParseError::at(line_number, line, word, expected))
+}
+
+/// Runs a nom `parser` over all of `line`, which must leave nothing behind.
+pub fn parse_line<'a, T, P>(
+    line_number: usize,
+    line: &'a str,
+    expected: &str,
+    mut parser: P,
+) -> Result<T, ParseError>
+where
+    P: FnMut(&'a str) -> nom::IResult<&'a str, T>,
+{
+    match parser(line) {
+        Ok(("", result)) => Ok(result),
+        Ok((rest, _)) => Err(ParseError::at(line_number, line, rest, "end of line")),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            Err(ParseError::at(line_number, line, e.input, expected))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(ParseError::end_of_line(line_number, line, expected)),
+    }
+}
@@ -0,0 +1,231 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+use Direction::*;
+
+/// A position (or offset) on a plane.  Following the puzzles' maps, `y` grows downward.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Point2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A position (or offset) in space.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Point2 {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Point2 { x, y }
+    }
+
+    /// The distance moving only horizontally or vertically.
+    pub fn manhattan(self, other: Self) -> i32 {
+        let difference = self - other;
+        difference.x.abs() + difference.y.abs()
+    }
+
+    /// The distance moving diagonally, too: the number of king's moves.
+    pub fn chebyshev(self, other: Self) -> i32 {
+        let difference = self - other;
+        difference.x.abs().max(difference.y.abs())
+    }
+
+    /// Each coordinate clamped to -1, 0, or 1: a single step in this point's general direction.
+    pub fn signum(self) -> Self {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    /// The four points that share an edge with this one.
+    pub fn neighbors4(self) -> impl Iterator<Item = Self> {
+        Direction::ALL
+            .into_iter()
+            .map(move |direction| self + direction.offset())
+    }
+
+    /// The eight points that share an edge or a corner with this one.
+    pub fn neighbors8(self) -> impl Iterator<Item = Self> {
+        Direction::ALL.into_iter().flat_map(move |direction| {
+            let edge = self + direction.offset();
+            // each edge, then the corner clockwise from it
+            [edge, edge + direction.turn_right().offset()]
+        })
+    }
+}
+
+impl Point3 {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Point3 { x, y, z }
+    }
+
+    pub fn manhattan(self, other: Self) -> i32 {
+        let difference = self - other;
+        difference.x.abs() + difference.y.abs() + difference.z.abs()
+    }
+
+    pub fn chebyshev(self, other: Self) -> i32 {
+        let difference = self - other;
+        difference
+            .x
+            .abs()
+            .max(difference.y.abs())
+            .max(difference.z.abs())
+    }
+
+    pub fn signum(self) -> Self {
+        Point3::new(self.x.signum(), self.y.signum(), self.z.signum())
+    }
+
+    /// The six points that share a face with this one.
+    pub fn neighbors6(self) -> impl Iterator<Item = Self> {
+        [
+            Point3::new(-1, 0, 0),
+            Point3::new(1, 0, 0),
+            Point3::new(0, -1, 0),
+            Point3::new(0, 1, 0),
+            Point3::new(0, 0, -1),
+            Point3::new(0, 0, 1),
+        ]
+        .into_iter()
+        .map(move |offset| self + offset)
+    }
+}
+
+macro_rules! arithmetic {
+    ($point: ident { $($field: ident),* }) => {
+        impl Add for $point {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                $point { $($field: self.$field + other.$field),* }
+            }
+        }
+
+        impl Sub for $point {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                $point { $($field: self.$field - other.$field),* }
+            }
+        }
+
+        impl Mul<i32> for $point {
+            type Output = Self;
+
+            fn mul(self, scale: i32) -> Self {
+                $point { $($field: self.$field * scale),* }
+            }
+        }
+
+        impl Neg for $point {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                $point { $($field: -self.$field),* }
+            }
+        }
+
+        impl AddAssign for $point {
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl SubAssign for $point {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+    };
+}
+
+arithmetic!(Point2 { x, y });
+arithmetic!(Point3 { x, y, z });
+
+/// One of the four ways to go on a map, where north is up (toward smaller `y`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise, starting from north.
+    pub const ALL: [Direction; 4] = [North, East, South, West];
+
+    /// A single step in this direction.
+    pub fn offset(self) -> Point2 {
+        match self {
+            North => Point2::new(0, -1),
+            East => Point2::new(1, 0),
+            South => Point2::new(0, 1),
+            West => Point2::new(-1, 0),
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            North => East,
+            East => South,
+            South => West,
+            West => North,
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        match self {
+            North => West,
+            East => North,
+            South => East,
+            West => South,
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        self.turn_right().turn_right()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let a = Point2::new(1, -2);
+        let b = Point2::new(-3, 1);
+
+        assert_eq!(a - b, Point2::new(4, -3));
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(a.chebyshev(b), 4);
+        assert_eq!((b - a).signum(), Point2::new(-1, 1));
+        assert_eq!(Point3::new(1, 2, 3).manhattan(Point3::default()), 6);
+    }
+
+    #[test]
+    fn directions() {
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.reverse().offset(), -direction.offset());
+        }
+        assert_eq!(North.turn_right(), East);
+
+        let origin = Point2::default();
+        let mut neighbors = origin.neighbors8().collect::<Vec<_>>();
+        neighbors.sort();
+        neighbors.dedup();
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.iter().all(|&n| n.chebyshev(origin) == 1));
+    }
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How far along a long-running part is: `done` steps out of `total`, if it knows how many there
+/// will be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// The error from a part that stopped early because its [`CancellationToken`] was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Asks whatever is running under [`with_progress`] to stop at the next [`report`].  Clones all
+/// share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+struct Reporter {
+    token: CancellationToken,
+    on_progress: Box<dyn FnMut(Progress)>,
+    last_percent: Option<u64>,
+}
+
+thread_local! {
+    static REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+}
+
+/// Called by a long-running loop every step, to say how far it's gotten; stops it with `Err` once
+/// it's been cancelled.  Does nothing outside of [`with_progress`].
+pub fn report(done: u64, total: Option<u64>) -> Result<(), Cancelled> {
+    REPORTER.with(|reporter| {
+        let mut reporter = reporter.borrow_mut();
+        let Some(reporter) = reporter.as_mut() else {
+            return Ok(());
+        };
+
+        // calling out on every step could take longer than the steps themselves, so only bother
+        // when a known total has moved by at least a percent
+        match total {
+            Some(total) if total > 0 => {
+                let percent = done * 100 / total;
+                if reporter.last_percent != Some(percent) {
+                    reporter.last_percent = Some(percent);
+                    (reporter.on_progress)(Progress {
+                        done,
+                        total: Some(total),
+                    });
+                }
+            }
+            _ => (reporter.on_progress)(Progress { done, total }),
+        }
+
+        if reporter.token.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Runs `f`, passing along everything it [`report`]s to `on_progress`, and stopping it once `token`
+/// is cancelled (which `on_progress` is welcome to do itself).
+pub fn with_progress<T>(
+    token: &CancellationToken,
+    on_progress: impl FnMut(Progress) + 'static,
+    f: impl FnOnce() -> T,
+) -> T {
+    let outer = REPORTER.with(|reporter| {
+        reporter.replace(Some(Reporter {
+            token: token.clone(),
+            on_progress: Box::new(on_progress),
+            last_percent: None,
+        }))
+    });
+    let _restore = RestoreReporter(outer);
+
+    f()
+}
+
+// puts back whichever reporter was there before, even if `f` panics
+struct RestoreReporter(Option<Reporter>);
+
+impl Drop for RestoreReporter {
+    fn drop(&mut self) {
+        REPORTER.with(|reporter| reporter.replace(self.0.take()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn cancels_from_the_callback() {
+        let token = CancellationToken::new();
+        let seen = Rc::new(RefCell::new(vec![]));
+
+        let result = with_progress(
+            &token,
+            {
+                let token = token.clone();
+                let seen = seen.clone();
+                move |progress| {
+                    seen.borrow_mut().push(progress.done);
+                    if progress.done == 300 {
+                        token.cancel();
+                    }
+                }
+            },
+            || {
+                for i in 0..1000 {
+                    report(i, Some(1000))?;
+                }
+                Ok(())
+            },
+        );
+
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(*seen.borrow(), (0..=300).step_by(10).collect::<Vec<_>>());
+        assert_eq!(report(0, None), Ok(()));
+    }
+
+    #[test]
+    fn stops_reporting_after_a_panic() {
+        let token = CancellationToken::new();
+        let result = std::panic::catch_unwind(|| {
+            with_progress(&token, |_| (), || panic!("gave up partway"));
+        });
+
+        assert!(result.is_err());
+        assert!(REPORTER.with(|reporter| reporter.borrow().is_none()));
+    }
+}
@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Everything a search found out: how far each node it reached is from the start, and the way it
+/// got there.
+#[derive(Debug, Clone)]
+pub struct Search<N, C> {
+    distances: HashMap<N, C>,
+    predecessors: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy> Search<N, C> {
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.distances.get(node).copied()
+    }
+
+    /// Every node that was reached, and how far it was from the start.
+    pub fn distances(&self) -> &HashMap<N, C> {
+        &self.distances
+    }
+
+    /// The node that each reached node was reached from (except for the start).
+    pub fn predecessors(&self) -> &HashMap<N, N> {
+        &self.predecessors
+    }
+
+    /// The nodes from the start to `node`, inclusive, if it was reached.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+
+        let mut path = vec![node.clone()];
+        while let Some(predecessor) = self.predecessors.get(path.last().unwrap()) {
+            path.push(predecessor.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// The first node that the `is_goal` passed to a `*_until` search or [`astar`] accepted, if
+    /// any.
+    pub fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+}
+
+/// Breadth-first search from `start`, where each step costs 1, visiting everything reachable.
+pub fn bfs<N, I>(start: N, successors: impl FnMut(&N) -> I) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    bfs_until(start, successors, |_| false)
+}
+
+/// Breadth-first search from `start`, stopping at the first (and so closest) node that `is_goal`
+/// accepts.
+pub fn bfs_until<N, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut search = Search {
+        distances: HashMap::from([(start.clone(), 0)]),
+        predecessors: HashMap::new(),
+        goal: None,
+    };
+    let mut to_visit = VecDeque::from([start]);
+
+    while let Some(node) = to_visit.pop_front() {
+        if is_goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        let distance = search.distances[&node] + 1;
+        for next in successors(&node) {
+            if search.distances.contains_key(&next) {
+                continue;
+            }
+            search.distances.insert(next.clone(), distance);
+            search.predecessors.insert(next.clone(), node.clone());
+            to_visit.push_back(next);
+        }
+    }
+
+    search
+}
+
+/// Dijkstra's algorithm from `start`, where `successors` gives each next node along with the cost
+/// of getting to it, visiting everything reachable.
+pub fn dijkstra<N, C, I>(start: N, successors: impl FnMut(&N) -> I) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, successors, |_| C::default(), |_| false)
+}
+
+/// Dijkstra's algorithm from `start`, stopping at the first (and so cheapest) node that `is_goal`
+/// accepts.
+pub fn dijkstra_until<N, C, I>(
+    start: N,
+    successors: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// A* search from `start` to the first node `is_goal` accepts.  `heuristic` has to be admissible
+/// (never more than the real remaining cost) for that to be the cheapest.
+pub fn astar<N, C, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut search = Search {
+        distances: HashMap::from([(start.clone(), C::default())]),
+        predecessors: HashMap::new(),
+        goal: None,
+    };
+    let mut to_visit = BinaryHeap::from([Entry {
+        priority: heuristic(&start),
+        cost: C::default(),
+        node: start,
+    }]);
+
+    while let Some(Entry { cost, node, .. }) = to_visit.pop() {
+        // there's already been a cheaper way here
+        if search.distances[&node] < cost {
+            continue;
+        }
+
+        if is_goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+
+        for (next, step) in successors(&node) {
+            let next_cost = cost + step;
+            if search
+                .distances
+                .get(&next)
+                .is_some_and(|&known| known <= next_cost)
+            {
+                continue;
+            }
+            search.distances.insert(next.clone(), next_cost);
+            search.predecessors.insert(next.clone(), node.clone());
+            to_visit.push(Entry {
+                priority: next_cost + heuristic(&next),
+                cost: next_cost,
+                node: next,
+            });
+        }
+    }
+
+    search
+}
+
+// ordered so that BinaryHeap, a max-heap, pops the lowest priority first; the node doesn't matter
+struct Entry<N, C> {
+    priority: C,
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for Entry<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<N, C: Ord> Eq for Entry<N, C> {}
+
+impl<N, C: Ord> PartialOrd for Entry<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N, C: Ord> Ord for Entry<N, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Point2;
+
+    // a 5x5 room with a wall down the middle, except at the bottom
+    fn open(point: &Point2) -> bool {
+        (0..5).contains(&point.x) && (0..5).contains(&point.y) && !(point.x == 2 && point.y < 4)
+    }
+
+    fn successors(point: &Point2) -> impl Iterator<Item = Point2> {
+        point.neighbors4().filter(open)
+    }
+
+    #[test]
+    fn bfs_around_a_wall() {
+        let start = Point2::new(0, 0);
+        let end = Point2::new(4, 0);
+
+        let search = bfs(start, successors);
+        assert_eq!(search.distance(&end), Some(12));
+        assert_eq!(search.distance(&Point2::new(2, 0)), None);
+        assert_eq!(search.distances().len(), 21);
+
+        let path = search.path_to(&end).unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!((path[0], path[12]), (start, end));
+        assert!(path.windows(2).all(|step| step[0].manhattan(step[1]) == 1));
+
+        let search = bfs_until(start, successors, |&point| point == Point2::new(0, 3));
+        assert_eq!(search.goal(), Some(&Point2::new(0, 3)));
+        assert!(search.distance(&end).is_none());
+    }
+
+    #[test]
+    fn weighted() {
+        let start = Point2::new(0, 0);
+        let end = Point2::new(4, 0);
+        // going down costs more than going anywhere else
+        let weighted = |point: &Point2| {
+            let point = *point;
+            successors(&point).map(move |next| (next, if next.y > point.y { 3 } else { 1 }))
+        };
+
+        assert_eq!(dijkstra(start, weighted).distance(&end), Some(20));
+
+        let search = astar(
+            start,
+            weighted,
+            |point| point.manhattan(end),
+            |&point| point == end,
+        );
+        assert_eq!(search.goal(), Some(&end));
+        assert_eq!(search.distance(&end), Some(20));
+        assert_eq!(search.path_to(&end).unwrap().len(), 13);
+    }
+}
@@ -0,0 +1,111 @@
+/// Something that plays out one step at a time, so that callers can look at it between steps (to
+/// draw it, or test it), or stop partway through, rather than only getting the final answer.
+pub trait Simulation {
+    /// What callers get to see between steps.
+    type State;
+
+    /// Advances by one step.  Does nothing useful once the simulation [is
+    /// done](Simulation::is_done).
+    fn step(&mut self);
+
+    fn state(&self) -> &Self::State;
+
+    /// Whether there's nothing left to do.  Some simulations go on forever, and never are.
+    fn is_done(&self) -> bool;
+
+    /// Steps until done, or until `steps` steps have gone by, and returns how many it took.
+    fn run_for(&mut self, steps: usize) -> usize {
+        let mut taken = 0;
+        while taken < steps && !self.is_done() {
+            self.step();
+            taken += 1;
+        }
+        taken
+    }
+
+    /// Steps until done, which is never for simulations that go on forever.
+    fn run(&mut self) {
+        while !self.is_done() {
+            self.step();
+        }
+    }
+
+    /// Copies of the state before each step, starting with the current one and ending with the one
+    /// it's done in (if it ever is).
+    fn snapshots(&mut self) -> Snapshots<'_, Self>
+    where
+        Self: Sized,
+        Self::State: Clone,
+    {
+        Snapshots {
+            simulation: self,
+            finished: false,
+        }
+    }
+}
+
+/// See [`Simulation::snapshots`].
+pub struct Snapshots<'a, S> {
+    simulation: &'a mut S,
+    finished: bool,
+}
+
+impl<S> Iterator for Snapshots<'_, S>
+where
+    S: Simulation,
+    S::State: Clone,
+{
+    type Item = S::State;
+
+    fn next(&mut self) -> Option<S::State> {
+        if self.finished {
+            return None;
+        }
+
+        let snapshot = self.simulation.state().clone();
+        if self.simulation.is_done() {
+            self.finished = true;
+        } else {
+            self.simulation.step();
+        }
+
+        Some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // halves an even number, or triples an odd one and adds one, until it gets to 1
+    struct Collatz(u32);
+
+    impl Simulation for Collatz {
+        type State = u32;
+
+        fn step(&mut self) {
+            self.0 = match self.0 % 2 {
+                0 => self.0 / 2,
+                _ => 3 * self.0 + 1,
+            };
+        }
+
+        fn state(&self) -> &u32 {
+            &self.0
+        }
+
+        fn is_done(&self) -> bool {
+            self.0 == 1
+        }
+    }
+
+    #[test]
+    fn steps() {
+        let mut collatz = Collatz(6);
+        assert_eq!(collatz.run_for(3), 3);
+        assert_eq!(collatz.state(), &5);
+        assert_eq!(collatz.snapshots().collect::<Vec<_>>(), [5, 16, 8, 4, 2, 1]);
+        assert!(collatz.is_done());
+        assert_eq!(collatz.run_for(3), 0);
+    }
+}
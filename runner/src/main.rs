@@ -0,0 +1,168 @@
+use std::io::Read;
+use std::process::ExitCode;
+
+use indicatif::ProgressBar;
+use prelude::instrument::{measure, Measurement};
+use prelude::progress::{with_progress, CancellationToken};
+use prelude::{Export, Generate};
+
+const USAGE: &str = "\
+usage: runner [--time | --export] <day> [input file, or - for stdin]
+       runner --generate <day> [seed] [size]";
+
+enum Mode {
+    Solve { time: bool },
+    // prints what the input was parsed into, as JSON, instead of solving it
+    Export,
+    // prints a made-up input instead of reading one
+    Generate { size: Option<usize>, seed: u64 },
+}
+
+fn run<S: Export + Generate>(mode: &Mode, path: Option<String>) -> Result<(), String> {
+    match *mode {
+        Mode::Solve { time } => solve::<S>(&read_input(path)?, time),
+        Mode::Export => {
+            let solution =
+                S::new(&read_input(path)?).map_err(|e| format!("couldn't parse input: {}", e))?;
+            println!("{}", solution.export());
+            Ok(())
+        }
+        Mode::Generate { size, seed } => {
+            print!(
+                "{}",
+                S::generate_seeded(seed, size.unwrap_or(S::PUZZLE_SIZE))
+            );
+            Ok(())
+        }
+    }
+}
+
+fn solve<S: Export>(input: &str, time: bool) -> Result<(), String> {
+    // describes how long something took, and what it counted along the way, if we were asked to
+    let report = |measurement: Measurement| -> String {
+        if !time {
+            return String::new();
+        }
+        let mut report = format!(" ({:?}", measurement.elapsed);
+        for (name, count) in measurement.counters {
+            report += &format!(", {}: {}", name, count);
+        }
+        report + ")"
+    };
+
+    let (solution, measurement) = measure(|| S::new(input));
+    let solution = solution.map_err(|e| format!("couldn't parse input: {}", e))?;
+    if time {
+        println!("Parsed{}", report(measurement));
+    }
+
+    let (part1, measurement) = measure(|| with_progress_bar(|| solution.part1()));
+    let part1 = part1.map_err(|e| e.to_string())?;
+    println!("Part 1: {}{}", part1, report(measurement));
+
+    let (part2, measurement) = measure(|| with_progress_bar(|| solution.part2()));
+    let part2 = part2.map_err(|e| e.to_string())?;
+    println!("Part 2: {}{}", part2, report(measurement));
+
+    Ok(())
+}
+
+// shows whatever `f` reports on stderr, if it's a terminal, and then gets out of the way
+fn with_progress_bar<T>(f: impl FnOnce() -> T) -> T {
+    let bar = ProgressBar::new_spinner();
+    let result = with_progress(
+        &CancellationToken::new(),
+        {
+            let bar = bar.clone();
+            move |progress| {
+                if let Some(total) = progress.total {
+                    bar.set_length(total);
+                }
+                bar.set_position(progress.done);
+            }
+        },
+        f,
+    );
+    bar.finish_and_clear();
+
+    result
+}
+
+fn read_input(path: Option<String>) -> Result<String, String> {
+    match path.as_deref() {
+        None | Some("-") => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("couldn't read stdin: {}", e))?;
+            Ok(input)
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("couldn't read {:?}: {}", path, e))
+        }
+    }
+}
+
+// whatever comes after the flags, for --generate
+fn parse_generate_args(mut args: impl Iterator<Item = String>) -> Option<(u32, Mode)> {
+    let day = args.next()?.parse().ok()?;
+    let seed = args.next().map_or(Ok(0), |seed| seed.parse()).ok()?;
+    let size = args.next().map(|size| size.parse()).transpose().ok()?;
+    Some((day, Mode::Generate { size, seed }))
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    let parsed = if args.next_if_eq("--generate").is_some() {
+        parse_generate_args(args).map(|(day, mode)| (day, mode, None))
+    } else {
+        let mode = if args.next_if_eq("--export").is_some() {
+            Mode::Export
+        } else {
+            Mode::Solve {
+                time: args.next_if_eq("--time").is_some(),
+            }
+        };
+        args.next()
+            .and_then(|day| day.parse::<u32>().ok())
+            .map(|day| (day, mode, args.next()))
+    };
+
+    let Some((day, mode, path)) = parsed else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = match day {
+        1 => run::<day_01::Solution>(&mode, path),
+        2 => run::<day_02::Solution>(&mode, path),
+        3 => run::<day_03::Solution>(&mode, path),
+        4 => run::<day_04::Solution>(&mode, path),
+        5 => run::<day_05::Solution>(&mode, path),
+        6 => run::<day_06::Solution>(&mode, path),
+        7 => run::<day_07::Solution>(&mode, path),
+        8 => run::<day_08::Solution>(&mode, path),
+        9 => run::<day_09::Solution>(&mode, path),
+        10 => run::<day_10::Solution>(&mode, path),
+        11 => run::<day_11::Solution>(&mode, path),
+        12 => run::<day_12::Solution>(&mode, path),
+        13 => run::<day_13::Solution>(&mode, path),
+        14 => run::<day_14::Solution>(&mode, path),
+        15 => run::<day_15::Solution>(&mode, path),
+        16 => run::<day_16::Solution>(&mode, path),
+        17 => run::<day_17::Solution>(&mode, path),
+        18 => run::<day_18::Solution>(&mode, path),
+        20 => run::<day_20::Solution>(&mode, path),
+        21 => run::<day_21::Solution>(&mode, path),
+        23 => run::<day_23::Solution>(&mode, path),
+        x => Err(format!("day {} hasn't been solved", x)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
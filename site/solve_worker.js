@@ -0,0 +1,64 @@
+// Runs everything that calls into a day's code off of the page's thread, so that the page can show
+// how a solve is going, and stop it; and so that a panic, which traps the whole module, only takes
+// this worker down with it.
+//
+// The page sends {type: "logLevel", level}, {type: "solve", day, input, config, timeLimit},
+// {type: "export", day, input, config} and {type: "generate", day, seed}.  This sends back
+// {type: "progress", done, total} along the way of a solve, then {type: "solved", result},
+// {type: "exported", json} or {type: "generated", input}; or {type: "error", message, logs}, after
+// which the page should start over with a new worker, in case that was a trap.
+import init, {
+    export as exportParsed,
+    generate,
+    set_log_capture,
+    set_log_level,
+    solve_instrumented,
+} from "./omnibus/omnibus.js";
+
+const ready = init().then(() => set_log_capture(true));
+
+self.addEventListener("message", async function(event) {
+    await ready;
+    const message = event.data;
+
+    if (message.type == "logLevel") {
+        set_log_level(message.level);
+        return;
+    }
+
+    try {
+        switch (message.type) {
+            case "solve":
+                self.postMessage({type: "solved", result: solve(message)});
+                break;
+            case "export":
+                self.postMessage({
+                    type: "exported",
+                    json: exportParsed(message.day, message.input, message.config),
+                });
+                break;
+            case "generate":
+                self.postMessage({
+                    type: "generated",
+                    input: generate(message.day, message.seed, undefined),
+                });
+                break;
+        }
+    } catch (e) {
+        self.postMessage({type: "error", message: e.message ?? String(e), logs: e.logs});
+    }
+});
+
+function solve(message) {
+    // there's no getting a message in here while the solver is running, so the time limit is the
+    // only thing that can cancel it from the inside
+    const deadline = message.timeLimit === undefined
+        ? undefined
+        : Date.now() + message.timeLimit * 1000;
+    function onProgress(done, total) {
+        self.postMessage({type: "progress", done, total});
+        return deadline === undefined || Date.now() < deadline;
+    }
+
+    return solve_instrumented(message.day, message.input, onProgress, message.config);
+}